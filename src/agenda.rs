@@ -0,0 +1,111 @@
+use chrono::{Local, NaiveDate};
+use serde::Serialize;
+use std::fmt;
+
+use crate::dates;
+use crate::notion::Task;
+
+/// When a task of the agenda is due, relative to today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    Overdue,
+    Today,
+    /// After today and up to Sunday.
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl Bucket {
+    pub const ALL: [Bucket; 5] = [Bucket::Overdue, Bucket::Today, Bucket::ThisWeek, Bucket::Later, Bucket::NoDate];
+
+    /// The bucket of `task` on `today`, using its due dates in the local
+    /// time zone. A date range that started before today and has not ended
+    /// is due today; finished tasks are never overdue.
+    pub fn of(task: &Task, today: NaiveDate) -> Bucket {
+        let Some(due) = task.due_date.map(|due| due.date_in(&Local)) else {
+            return Bucket::NoDate;
+        };
+        if task.is_overdue(today) {
+            Bucket::Overdue
+        } else if due <= today {
+            Bucket::Today
+        } else if due <= dates::end_of_week(today) {
+            Bucket::ThisWeek
+        } else {
+            Bucket::Later
+        }
+    }
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Bucket::Overdue => "Overdue",
+            Bucket::Today => "Today",
+            Bucket::ThisWeek => "This week",
+            Bucket::Later => "Later",
+            Bucket::NoDate => "No date",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The tasks of one bucket.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Group {
+    pub bucket: Bucket,
+    pub tasks: Vec<Task>,
+}
+
+/// Sorts `tasks` into a group per bucket, in the order of [`Bucket::ALL`],
+/// empty ones included. Tasks keep their order within a group.
+pub fn group(tasks: Vec<Task>, today: NaiveDate) -> Vec<Group> {
+    let mut groups = Bucket::ALL.map(|bucket| Group { bucket, tasks: Vec::new() });
+    for task in tasks {
+        let bucket = Bucket::of(&task, today);
+        groups[bucket as usize].tasks.push(task);
+    }
+    groups.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(title: &str, due: Option<&str>) -> Task {
+        Task { due_date: due.map(|due| due.parse().unwrap()), ..Task::for_test(title) }
+    }
+
+    #[test]
+    fn test_group_by_due_date() {
+        // A Wednesday; the week ends on Sunday the 21st.
+        let today = NaiveDate::from_ymd_opt(2024, 1, 17).unwrap();
+        let tasks = vec![
+            task("later", Some("2024-01-22")),
+            task("late", Some("2024-01-16")),
+            task("someday", None),
+            task("sunday", Some("2024-01-21")),
+            task("now", Some("2024-01-17")),
+            task("thursday", Some("2024-01-18")),
+            task("running", Some("2024-01-15/2024-01-19")),
+            task("ended", Some("2024-01-15/2024-01-16")),
+        ];
+
+        let titles = group(tasks, today)
+            .into_iter()
+            .map(|group| (group.bucket.to_string(), group.tasks.into_iter().map(|t| t.title).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            titles,
+            vec![
+                ("Overdue".to_string(), vec!["late".to_string(), "ended".to_string()]),
+                ("Today".to_string(), vec!["now".to_string(), "running".to_string()]),
+                ("This week".to_string(), vec!["sunday".to_string(), "thursday".to_string()]),
+                ("Later".to_string(), vec!["later".to_string()]),
+                ("No date".to_string(), vec!["someday".to_string()]),
+            ]
+        );
+    }
+}
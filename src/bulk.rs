@@ -0,0 +1,92 @@
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use std::fmt;
+
+use crate::notion::{NotionClient, Task, TaskPriority, TaskStatus, TaskUpdate};
+use crate::tags;
+
+/// How many requests a bulk change keeps in flight. The client's rate
+/// limiter still decides when each one is sent.
+pub const CONCURRENCY: usize = 3;
+
+/// A change applied to every task selected by a bulk command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkAction {
+    Status(TaskStatus),
+    Priority(TaskPriority),
+    /// Adds tags, keeping the ones each task already has.
+    AddTags(Vec<String>),
+    /// Replaces the tags of `from` with `to`, for renaming and merging tags.
+    ReplaceTags { from: Vec<String>, to: String },
+    /// Archives the tasks, like `delete`.
+    Delete,
+}
+
+impl BulkAction {
+    /// The update sent for `task`, or `None` when the action is a delete.
+    pub fn update_for(&self, task: &Task) -> Option<TaskUpdate> {
+        match self {
+            BulkAction::Status(status) => Some(TaskUpdate::new().status(status.clone())),
+            BulkAction::Priority(priority) => Some(TaskUpdate::new().priority(priority.clone())),
+            BulkAction::AddTags(tags) => Some(TaskUpdate::new().tags(tags::add(&task.tags, tags))),
+            BulkAction::ReplaceTags { from, to } => Some(TaskUpdate::new().tags(tags::replace(&task.tags, from, to))),
+            BulkAction::Delete => None,
+        }
+    }
+}
+
+impl fmt::Display for BulkAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BulkAction::Status(status) => write!(f, "set status to {}", status),
+            BulkAction::Priority(priority) => write!(f, "set priority to {}", priority),
+            BulkAction::AddTags(tags) => write!(f, "add tags {}", tags.join(", ")),
+            BulkAction::ReplaceTags { from, to } => write!(f, "replace tags {} with {}", from.join(", "), to),
+            BulkAction::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// What happened to one task of a bulk change.
+#[derive(Debug)]
+pub struct BulkOutcome {
+    /// The task as it was selected.
+    pub task: Task,
+    /// The updated task, `None` once deleted, or why the change failed.
+    pub result: Result<Option<Task>>,
+}
+
+/// Applies `action` to every task, `CONCURRENCY` at a time. A failure does
+/// not stop the other tasks; outcomes are returned in the order of `tasks`.
+pub async fn apply(client: &NotionClient, tasks: Vec<Task>, action: &BulkAction) -> Vec<BulkOutcome> {
+    stream::iter(tasks)
+        .map(|task| async move {
+            let result = match action.update_for(&task) {
+                Some(update) => client.update_task(&task.id, &update).await.map(Some),
+                None => client.delete_task(&task.id).await.map(|()| None),
+            };
+            BulkOutcome { task, result }
+        })
+        .buffered(CONCURRENCY)
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(tags: &[&str]) -> Task {
+        Task { tags: tags.iter().map(|t| t.to_string()).collect(), ..Task::for_test("Ship") }
+    }
+
+    #[test]
+    fn test_add_tags_keeps_existing_tags() {
+        let action = BulkAction::AddTags(vec!["sprint-12".to_string(), "dev".to_string()]);
+        let expected = TaskUpdate::new().tags(vec!["dev".to_string(), "sprint-12".to_string()]);
+
+        assert_eq!(action.update_for(&task(&["dev"])), Some(expected));
+        assert_eq!(action.to_string(), "add tags sprint-12, dev");
+        assert_eq!(BulkAction::Delete.update_for(&task(&[])), None);
+    }
+}
@@ -1,24 +1,544 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-#[allow(dead_code)]
-#[derive(Debug)]
+use crate::secrets::{self, TokenSource};
+
+/// The Notion property types a task field can be mapped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyKind {
+    Title,
+    RichText,
+    Status,
+    Select,
+    MultiSelect,
+    Date,
+}
+
+impl PropertyKind {
+    /// The property type name used by the Notion API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PropertyKind::Title => "title",
+            PropertyKind::RichText => "rich_text",
+            PropertyKind::Status => "status",
+            PropertyKind::Select => "select",
+            PropertyKind::MultiSelect => "multi_select",
+            PropertyKind::Date => "date",
+        }
+    }
+}
+
+impl fmt::Display for PropertyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for PropertyKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "title" => Ok(PropertyKind::Title),
+            "rich_text" | "text" => Ok(PropertyKind::RichText),
+            "status" => Ok(PropertyKind::Status),
+            "select" => Ok(PropertyKind::Select),
+            "multi_select" => Ok(PropertyKind::MultiSelect),
+            "date" => Ok(PropertyKind::Date),
+            other => Err(anyhow!("Invalid property type: {}", other)),
+        }
+    }
+}
+
+/// A database property a task field is read from and written to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertyRef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: PropertyKind,
+}
+
+impl PropertyRef {
+    pub fn new(name: impl Into<String>, kind: PropertyKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+        }
+    }
+}
+
+/// Maps each `Task` field to a property of the Notion database.
+///
+/// The defaults match the database layout described in the README.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PropertyMap {
+    pub title: PropertyRef,
+    pub status: PropertyRef,
+    pub priority: PropertyRef,
+    pub due_date: PropertyRef,
+    pub tags: PropertyRef,
+    pub description: PropertyRef,
+}
+
+impl Default for PropertyMap {
+    fn default() -> Self {
+        Self {
+            title: PropertyRef::new("Name", PropertyKind::Title),
+            status: PropertyRef::new("Status", PropertyKind::Status),
+            priority: PropertyRef::new("Priority", PropertyKind::Select),
+            due_date: PropertyRef::new("Due Date", PropertyKind::Date),
+            tags: PropertyRef::new("Tags", PropertyKind::MultiSelect),
+            description: PropertyRef::new("Description", PropertyKind::RichText),
+        }
+    }
+}
+
+impl PropertyMap {
+    /// Every mapped field with its logical name, in display order.
+    pub fn fields(&self) -> [(&'static str, &PropertyRef); 6] {
+        [
+            ("title", &self.title),
+            ("status", &self.status),
+            ("priority", &self.priority),
+            ("due_date", &self.due_date),
+            ("tags", &self.tags),
+            ("description", &self.description),
+        ]
+    }
+
+    /// The property types each logical field may be mapped to.
+    pub fn allowed_kinds(field: &str) -> &'static [PropertyKind] {
+        match field {
+            "title" => &[PropertyKind::Title],
+            "status" => &[PropertyKind::Status, PropertyKind::Select],
+            "priority" => &[PropertyKind::Select, PropertyKind::Status],
+            "due_date" => &[PropertyKind::Date],
+            "tags" => &[PropertyKind::MultiSelect],
+            "description" => &[PropertyKind::RichText],
+            _ => &[],
+        }
+    }
+
+    fn field_mut(&mut self, field: &str) -> Option<&mut PropertyRef> {
+        match field {
+            "title" => Some(&mut self.title),
+            "status" => Some(&mut self.status),
+            "priority" => Some(&mut self.priority),
+            "due_date" => Some(&mut self.due_date),
+            "tags" => Some(&mut self.tags),
+            "description" => Some(&mut self.description),
+            _ => None,
+        }
+    }
+
+    /// Maps `field` to `property`, rejecting property types the field cannot use.
+    pub fn set(&mut self, field: &str, property: PropertyRef) -> Result<()> {
+        if !Self::allowed_kinds(field).contains(&property.kind) {
+            return Err(anyhow!(
+                "Field {} cannot be mapped to a {} property",
+                field,
+                property.kind
+            ));
+        }
+        let slot = self
+            .field_mut(field)
+            .ok_or_else(|| anyhow!("Unknown task field: {}", field))?;
+        *slot = property;
+        Ok(())
+    }
+
+    /// Applies `NOTION_PROPERTY_<FIELD>` overrides, e.g.
+    /// `NOTION_PROPERTY_STATUS=State:select` or `NOTION_PROPERTY_DUE_DATE=Deadline`.
+    /// The type defaults to the one currently mapped.
+    pub fn apply_env(&mut self) -> Result<()> {
+        for field in ["title", "status", "priority", "due_date", "tags", "description"] {
+            let var = format!("NOTION_PROPERTY_{}", field.to_uppercase());
+            let Ok(value) = env::var(&var) else {
+                continue;
+            };
+
+            let current = self.field_mut(field).map(|p| p.kind).unwrap_or(PropertyKind::RichText);
+            let property = match value.rsplit_once(':') {
+                Some((name, kind)) => PropertyRef::new(name.trim(), kind.parse()?),
+                None => PropertyRef::new(value.trim(), current),
+            };
+            self.set(field, property).map_err(|e| anyhow!("{}: {}", var, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Values used by commands when the matching option is not given.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Defaults {
+    /// Status of new tasks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Priority of new tasks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Tags added to new tasks.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Number of tasks fetched per request by `list`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<u32>,
+    /// Template tasks are printed with, as for `--format`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// One named workspace/database in the configuration file.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// A plaintext token; `auth login` keeps it in the keyring instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database_id: Option<String>,
+    pub properties: PropertyMap,
+    pub defaults: Defaults,
+}
+
+impl fmt::Debug for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Profile")
+            .field("token", &self.token.as_ref().map(|_| Redacted))
+            .field("database_id", &self.database_id)
+            .field("properties", &self.properties)
+            .field("defaults", &self.defaults)
+            .finish()
+    }
+}
+
+/// Stands in for a token in `Debug` output.
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<redacted>")
+    }
+}
+
+/// The TOML configuration file, e.g.
+///
+/// ```toml
+/// default_profile = "team"
+///
+/// [profiles.team]
+/// token = "secret_..."
+/// database_id = "..."
+/// properties.due_date = { name = "Deadline", type = "date" }
+/// defaults.priority = "Medium"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ConfigFile {
+    /// `$NOTION_CONFIG`, or `notion-cli-rs/config.toml` in the user's config
+    /// directory (`$XDG_CONFIG_HOME` or `~/.config` on Linux).
+    pub fn default_path() -> Option<PathBuf> {
+        match env::var_os("NOTION_CONFIG") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::config_dir().map(|dir| dir.join("notion-cli-rs").join("config.toml")),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Reads the file at `path`; a missing file is an empty configuration.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).with_context(|| format!("Invalid config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Cannot read config file {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        // Profiles may hold tokens, so keep the file private to the user.
+        write_private(path, toml::to_string_pretty(self)?.as_bytes())
+            .with_context(|| format!("Cannot write config file {}", path.display()))
+    }
+
+    /// The profile to use: `requested` when given, then `default_profile`,
+    /// then a profile called `default`. Returns `None` when nothing is
+    /// configured; asking for a profile that does not exist is an error.
+    pub fn select(&self, requested: Option<&str>) -> Result<Option<(&str, &Profile)>> {
+        let name = match requested.or(self.default_profile.as_deref()) {
+            Some(name) => name,
+            None if self.profiles.contains_key("default") => "default",
+            None => return Ok(None),
+        };
+
+        match self.profiles.get_key_value(name) {
+            Some((name, profile)) => Ok(Some((name.as_str(), profile))),
+            None if self.profiles.is_empty() => Err(anyhow!("Profile {} not found: no profiles are configured", name)),
+            None => Err(anyhow!(
+                "Profile {} not found. Available profiles: {}",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct Config {
     pub notion_token: String,
     pub database_id: String,
+    pub properties: PropertyMap,
+    pub defaults: Defaults,
+    /// The profile the values were read from, if any.
+    pub profile: Option<String>,
+    /// Where `notion_token` was read from; `None` when it was set directly.
+    pub token_source: Option<TokenSource>,
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("notion_token", &Redacted)
+            .field("database_id", &self.database_id)
+            .field("properties", &self.properties)
+            .field("defaults", &self.defaults)
+            .field("profile", &self.profile)
+            .field("token_source", &self.token_source)
+            .finish()
+    }
 }
 
-#[allow(dead_code)]
 impl Config {
+    /// Loads the default profile; see [`Config::load`].
     pub fn new() -> Result<Self> {
-        let notion_token = env::var("NOTION_TOKEN")
-            .map_err(|_| anyhow!("NOTION_TOKEN environment variable not set"))?;
-        let database_id = env::var("NOTION_DATABASE_ID")
-            .map_err(|_| anyhow!("NOTION_DATABASE_ID environment variable not set"))?;
+        Self::load(None)
+    }
+
+    /// Builds the configuration from a profile of the configuration file,
+    /// overridden by `NOTION_TOKEN`, `NOTION_DATABASE_ID` and
+    /// `NOTION_PROPERTY_<FIELD>`. The profile is `profile`, else
+    /// `$NOTION_PROFILE`, else the file's default one.
+    ///
+    /// Without a token in the environment or the profile, the token saved by
+    /// `auth login` is read from the keyring or the encrypted token file.
+    pub fn load(profile: Option<&str>) -> Result<Self> {
+        let mut config = Self::load_unchecked(profile)?;
+        if config.token_source.is_none() {
+            if let Some((token, source)) = secrets::stored_token(config.account())? {
+                config.notion_token = token;
+                config.token_source = Some(source);
+            }
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Like [`Config::load`], without looking up stored tokens and without
+    /// failing on a missing token or database.
+    pub fn load_unchecked(profile: Option<&str>) -> Result<Self> {
+        let file = match ConfigFile::default_path() {
+            Some(path) => ConfigFile::load(&path)?,
+            None => ConfigFile::default(),
+        };
+        let requested = profile.map(str::to_string).or_else(|| env::var("NOTION_PROFILE").ok());
+
+        let mut config = Self::from_file(&file, requested.as_deref())?;
+        if let Ok(token) = env::var("NOTION_TOKEN") {
+            config.notion_token = token;
+            config.token_source = Some(TokenSource::Environment);
+        }
+        if let Ok(database_id) = env::var("NOTION_DATABASE_ID") {
+            config.database_id = database_id;
+        }
+        config.properties.apply_env()?;
+        Ok(config)
+    }
+
+    /// The name the token of this configuration is stored under: the
+    /// profile name, or `default` without a profile.
+    pub fn account(&self) -> &str {
+        self.profile.as_deref().unwrap_or("default")
+    }
+
+    /// The configuration stored in `file` alone, without environment overrides.
+    pub fn from_file(file: &ConfigFile, profile: Option<&str>) -> Result<Self> {
+        let Some((name, profile)) = file.select(profile)? else {
+            return Ok(Self::default());
+        };
 
         Ok(Config {
-            notion_token,
-            database_id,
+            notion_token: profile.token.clone().unwrap_or_default(),
+            database_id: profile.database_id.clone().unwrap_or_default(),
+            properties: profile.properties.clone(),
+            defaults: profile.defaults.clone(),
+            profile: Some(name.to_string()),
+            token_source: profile.token.as_ref().map(|_| TokenSource::ConfigFile),
         })
     }
-} 
\ No newline at end of file
+
+    fn check(&self) -> Result<()> {
+        let source = match &self.profile {
+            Some(name) => format!("in profile {} of the config file", name),
+            None => "in a config file profile".to_string(),
+        };
+        if self.notion_token.is_empty() {
+            return Err(anyhow!(
+                "No Notion token configured: run `notion-cli-rs auth login`, or set NOTION_TOKEN or `token` {}",
+                source
+            ));
+        }
+        if self.database_id.is_empty() {
+            return Err(anyhow!("No database configured: set NOTION_DATABASE_ID or `database_id` {}", source));
+        }
+        Ok(())
+    }
+}
+
+/// Writes `contents` to `path`, readable by the user only. A new file is
+/// created private and an existing one is made private before anything is
+/// written, so the contents are never readable by others.
+pub(crate) fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    #[cfg(unix)]
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_property_map() {
+        let map = PropertyMap::default();
+        assert_eq!(map.status, PropertyRef::new("Status", PropertyKind::Status));
+        assert_eq!(map.due_date, PropertyRef::new("Due Date", PropertyKind::Date));
+    }
+
+    #[test]
+    fn test_set_rejects_incompatible_kind() {
+        let mut map = PropertyMap::default();
+        assert!(map.set("due_date", PropertyRef::new("Deadline", PropertyKind::Select)).is_err());
+        assert!(map.set("status", PropertyRef::new("State", PropertyKind::Select)).is_ok());
+        assert_eq!(map.status.name, "State");
+    }
+
+    #[test]
+    fn test_property_kind_from_str() {
+        assert_eq!("multi-select".parse::<PropertyKind>().unwrap(), PropertyKind::MultiSelect);
+        assert_eq!("text".parse::<PropertyKind>().unwrap(), PropertyKind::RichText);
+        assert!("formula".parse::<PropertyKind>().is_err());
+    }
+
+    const SAMPLE: &str = r#"
+default_profile = "team"
+
+[profiles.team]
+token = "secret_team"
+database_id = "team-db"
+properties.status = { name = "State", type = "select" }
+
+[profiles.team.defaults]
+priority = "Medium"
+tags = ["work"]
+format = "{status_symbol} {title}"
+
+[profiles.personal]
+token = "secret_me"
+database_id = "personal-db"
+"#;
+
+    #[test]
+    fn test_select_default_profile() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let config = Config::from_file(&file, None).unwrap();
+
+        assert_eq!(config.profile.as_deref(), Some("team"));
+        assert_eq!(config.database_id, "team-db");
+        assert_eq!(config.properties.status, PropertyRef::new("State", PropertyKind::Select));
+        assert_eq!(config.properties.title, PropertyRef::new("Name", PropertyKind::Title));
+        assert_eq!(config.defaults.priority.as_deref(), Some("Medium"));
+        assert_eq!(config.defaults.tags, vec!["work"]);
+        assert_eq!(config.defaults.format.as_deref(), Some("{status_symbol} {title}"));
+    }
+
+    #[test]
+    fn test_select_named_profile() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let config = Config::from_file(&file, Some("personal")).unwrap();
+
+        assert_eq!(config.notion_token, "secret_me");
+        assert_eq!(config.properties, PropertyMap::default());
+    }
+
+    #[test]
+    fn test_unknown_profile_lists_available_ones() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let err = Config::from_file(&file, Some("work")).unwrap_err();
+
+        assert_eq!(err.to_string(), "Profile work not found. Available profiles: personal, team");
+    }
+
+    #[test]
+    fn test_config_file_round_trip() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let written = toml::to_string_pretty(&file).unwrap();
+
+        assert_eq!(ConfigFile::parse(&written).unwrap(), file);
+    }
+
+    #[test]
+    fn test_debug_output_redacts_token() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let config = Config::from_file(&file, None).unwrap();
+
+        assert_eq!(config.token_source, Some(TokenSource::ConfigFile));
+        assert!(!format!("{:?}", config).contains("secret_team"));
+        assert!(!format!("{:?}", file).contains("secret_team"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_saved_config_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("notion-cli-rs-config-{}", std::process::id()));
+        let path = dir.join("config.toml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        ConfigFile::parse(SAMPLE).unwrap().save(&path).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,557 @@
+use anyhow::{anyhow, Result};
+use chrono::{
+    DateTime, Datelike, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A date as Notion stores it: a whole day, or a moment with a UTC offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateValue {
+    Day(NaiveDate),
+    Time(DateTime<FixedOffset>),
+}
+
+impl DateValue {
+    /// The day this falls on in `zone`.
+    pub fn date_in<Z: TimeZone>(&self, zone: &Z) -> NaiveDate {
+        match self {
+            DateValue::Day(date) => *date,
+            DateValue::Time(time) => time.with_timezone(zone).date_naive(),
+        }
+    }
+
+    /// `2024-01-20`, or `2024-01-20 14:00` with the time in `zone`.
+    pub fn format_in<Z: TimeZone>(&self, zone: &Z) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        match self {
+            DateValue::Day(date) => date.format("%Y-%m-%d").to_string(),
+            DateValue::Time(time) => time.with_timezone(zone).format("%Y-%m-%d %H:%M").to_string(),
+        }
+    }
+
+    /// Reads a date as Notion returns it: `2024-01-20`,
+    /// `2024-01-20T14:00:00.000+02:00`, or without an offset, in which case
+    /// it is in `zone` (UTC when `None`).
+    fn from_iso(text: &str, zone: Option<Tz>) -> Option<Self> {
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Some(DateValue::Day(date));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+            return Some(DateValue::Time(time));
+        }
+        let datetime = NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        at(datetime, &zone.unwrap_or(Tz::UTC))
+    }
+}
+
+/// The ISO 8601 form Notion expects: `2024-01-20` or
+/// `2024-01-20T14:00:00+02:00`.
+impl fmt::Display for DateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateValue::Day(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            DateValue::Time(time) => write!(f, "{}", time.format("%Y-%m-%dT%H:%M:%S%:z")),
+        }
+    }
+}
+
+/// The value of a Notion date property: a start day or time, an optional
+/// end, and the time zone Notion shows the times in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DueDate {
+    pub start: DateValue,
+    pub end: Option<DateValue>,
+    /// Only kept for timed dates; all-day dates have no zone.
+    pub time_zone: Option<Tz>,
+}
+
+impl From<DateValue> for DueDate {
+    fn from(start: DateValue) -> Self {
+        Self { start, end: None, time_zone: None }
+    }
+}
+
+impl DueDate {
+    /// Parses a due date typed on the command line; `start` and `end` take
+    /// any form [`parse_date_at`] accepts. Times without an offset are in
+    /// `time_zone` (an IANA name such as `Europe/Paris`) when given, and in
+    /// the local time zone otherwise.
+    pub fn parse(start: &str, end: Option<&str>, time_zone: Option<&str>) -> Result<Self> {
+        match time_zone.map(parse_time_zone).transpose()? {
+            Some(zone) => Self::parse_at(start, end, Some(zone), Utc::now().with_timezone(&zone)),
+            None => Self::parse_at(start, end, None, Local::now()),
+        }
+    }
+
+    /// [`DueDate::parse`] relative to `now`, which should be in `time_zone`
+    /// when one is given.
+    pub fn parse_at<Z: TimeZone>(start: &str, end: Option<&str>, time_zone: Option<Tz>, now: DateTime<Z>) -> Result<Self> {
+        let due = Self {
+            start: parse_date_at(start, now.clone())?,
+            end: end.map(|end| parse_date_at(end, now)).transpose()?,
+            time_zone,
+        };
+        if let Some(end) = due.end {
+            let before = match (due.start, end) {
+                (DateValue::Time(start), DateValue::Time(end)) => end < start,
+                (start, end) => end.date_in(&Utc) < start.date_in(&Utc),
+            };
+            if before {
+                return Err(anyhow!("The end date {} is before the start date {}", end, due.start));
+            }
+        }
+        Ok(due.normalized())
+    }
+
+    /// Drops the time zone of all-day dates, which Notion ignores.
+    fn normalized(mut self) -> Self {
+        if self.is_all_day() {
+            self.time_zone = None;
+        }
+        self
+    }
+
+    /// Whether the start has no time of day.
+    pub fn is_all_day(&self) -> bool {
+        matches!(self.start, DateValue::Day(_))
+    }
+
+    /// The day the task is due (the start), in `zone`.
+    pub fn date_in<Z: TimeZone>(&self, zone: &Z) -> NaiveDate {
+        self.start.date_in(zone)
+    }
+
+    /// The last day of the date in `zone`: the end of a range, or the
+    /// start when there is no end.
+    pub fn end_date_in<Z: TimeZone>(&self, zone: &Z) -> NaiveDate {
+        self.end.unwrap_or(self.start).date_in(zone)
+    }
+
+    /// The date as people read it, with times in `zone`:
+    /// `2026-11-01 14:00 → 16:00` or `2026-11-01 → 2026-11-03`.
+    pub fn format_in<Z: TimeZone>(&self, zone: &Z) -> String
+    where
+        Z::Offset: fmt::Display,
+    {
+        let start = self.start.format_in(zone);
+        match self.end {
+            None => start,
+            Some(DateValue::Time(end)) if self.start.date_in(zone) == end.with_timezone(zone).date_naive() => {
+                format!("{} → {}", start, end.with_timezone(zone).format("%H:%M"))
+            }
+            Some(end) => format!("{} → {}", start, end.format_in(zone)),
+        }
+    }
+
+    /// The ISO 8601 form used in JSON, YAML and CSV output, with `/` between
+    /// the start and the end of a range. Times keep their offset but not
+    /// the time zone name.
+    pub fn to_iso(&self) -> String {
+        match self.end {
+            Some(end) => format!("{}/{}", self.start, end),
+            None => self.start.to_string(),
+        }
+    }
+
+    /// Notion's date object. Times are sent without an offset when there is
+    /// a time zone, as Notion requires; absent fields are left out.
+    pub fn to_notion(&self) -> serde_json::Value {
+        let text = |value: DateValue| match (value, self.time_zone) {
+            (DateValue::Time(time), Some(zone)) => time.with_timezone(&zone).format("%Y-%m-%dT%H:%M:%S").to_string(),
+            _ => value.to_string(),
+        };
+        let mut object = serde_json::json!({ "start": text(self.start) });
+        if let Some(end) = self.end {
+            object["end"] = serde_json::json!(text(end));
+        }
+        if let Some(zone) = self.time_zone {
+            object["time_zone"] = serde_json::json!(zone.name());
+        }
+        object
+    }
+
+    /// Reads Notion's date object; `None` when it is `null` or its start is
+    /// not a date. Unknown time zones are ignored.
+    pub fn from_notion(value: &serde_json::Value) -> Option<Self> {
+        let time_zone = value["time_zone"].as_str().and_then(|name| name.parse::<Tz>().ok());
+        let date = |key: &str| value[key].as_str().and_then(|text| DateValue::from_iso(text, time_zone));
+        let due = Self { start: date("start")?, end: date("end"), time_zone };
+        Some(due.normalized())
+    }
+}
+
+/// Shown in the local time zone.
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format_in(&Local))
+    }
+}
+
+/// Reads the form of [`DueDate::to_iso`]: `2024-01-20`,
+/// `2024-01-20T14:00:00+02:00`, or a `start/end` range of those.
+impl FromStr for DueDate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid ISO 8601 date \"{}\"", s);
+        let (start, end) = match s.split_once('/') {
+            Some((start, end)) => (start, Some(end)),
+            None => (s, None),
+        };
+        Ok(Self {
+            start: DateValue::from_iso(start, None).ok_or_else(invalid)?,
+            end: end.map(|end| DateValue::from_iso(end, None).ok_or_else(invalid)).transpose()?,
+            time_zone: None,
+        })
+    }
+}
+
+impl Serialize for DueDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_iso())
+    }
+}
+
+impl<'de> Deserialize<'de> for DueDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Checks an IANA time zone name such as `Europe/Paris`.
+pub fn parse_time_zone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>()
+        .map_err(|_| anyhow!("Unknown time zone \"{}\": use an IANA name such as Europe/Paris", name))
+}
+
+/// Parses a date typed on the command line, relative to the current local
+/// time. See [`parse_date_at`] for the accepted forms.
+pub fn parse_date(input: &str) -> Result<DateValue> {
+    parse_date_at(input, Local::now())
+}
+
+/// Parses a date relative to `now`:
+///
+/// - `2024-01-20`, or with a time: `2024-01-20T14:00`, `2024-01-20 14:00:30`,
+///   `2024-01-20T14:00Z`, `2024-01-20T14:00+02:00`
+/// - `today`, `tomorrow`, `yesterday`
+/// - a weekday (`fri`, `friday`: the next one, today included) or
+///   `next friday` (never today)
+/// - `next week` (its Monday), `next month`, `next year` (their first day)
+/// - `end of week` (Sunday), `end of month`, `end of year`, or `eow`, `eom`,
+///   `eoy`
+/// - offsets: `+3d`, `-1w`, `+2m`, `+1y`, `in 3 days`, `2 weeks ago`
+///
+/// Any form but a full timestamp can end with a time, e.g. `tomorrow 9:30`
+/// or `fri at 14:00`. Times without an offset are in the time zone of `now`.
+pub fn parse_date_at<Z: TimeZone>(input: &str, now: DateTime<Z>) -> Result<DateValue> {
+    let text = input.trim().to_lowercase();
+    if text.is_empty() {
+        return Err(anyhow!("The date is empty"));
+    }
+    let zone = now.timezone();
+    if let Some(value) = parse_timestamp(&text, &zone) {
+        return Ok(value);
+    }
+
+    // A trailing time applies to whatever date precedes it.
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    let (date_words, time) = match words.split_last() {
+        Some((last, rest)) if !rest.is_empty() => match parse_time(last) {
+            Some(time) => (rest.strip_suffix(&["at"]).unwrap_or(rest), Some(time)),
+            None => (&words[..], None),
+        },
+        _ => (&words[..], None),
+    };
+
+    let date = parse_day(&date_words.join(" "), now.date_naive()).ok_or_else(|| invalid(input))?;
+    match time {
+        None => Ok(DateValue::Day(date)),
+        Some(time) => at(date.and_time(time), &zone).ok_or_else(|| invalid(input)),
+    }
+}
+
+fn invalid(input: &str) -> anyhow::Error {
+    anyhow!(
+        "Invalid date \"{}\": use YYYY-MM-DD, YYYY-MM-DDTHH:MM[+HH:MM], today, tomorrow, a weekday, \
+         next week, end of month or an offset such as +3d",
+        input.trim()
+    )
+}
+
+/// `datetime` read in `zone`; `None` when it is skipped by a clock change.
+fn at<Z: TimeZone>(datetime: NaiveDateTime, zone: &Z) -> Option<DateValue> {
+    zone.from_local_datetime(&datetime).earliest().map(|time| DateValue::Time(time.fixed_offset()))
+}
+
+/// `2024-01-20T14:00`, with optional seconds, `T` or a space, and an
+/// optional `Z` or `±HH:MM` offset.
+fn parse_timestamp<Z: TimeZone>(text: &str, zone: &Z) -> Option<DateValue> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(&text.to_uppercase()) {
+        return Some(DateValue::Time(time));
+    }
+    let text = text.replacen(' ', "t", 1);
+    for format in ["%Y-%m-%dt%H:%M%#z", "%Y-%m-%dt%H:%M:%S%#z"] {
+        if let Ok(time) = DateTime::parse_from_str(&text.replace('z', "+00:00"), format) {
+            return Some(DateValue::Time(time));
+        }
+    }
+    for format in ["%Y-%m-%dt%H:%M", "%Y-%m-%dt%H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(&text, format) {
+            return at(datetime, zone);
+        }
+    }
+    None
+}
+
+/// `14:00`, `9:30` or `14:00:30`.
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(text, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M:%S"))
+        .ok()
+}
+
+fn parse_weekday(text: &str) -> Option<Weekday> {
+    match text {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The first `weekday` on or after `from`.
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Days::new(days as u64)
+}
+
+fn start_of_week(date: NaiveDate) -> NaiveDate {
+    date - Days::new(date.weekday().num_days_from_monday() as u64)
+}
+
+/// The Sunday ending the week of `date`.
+pub fn end_of_week(date: NaiveDate) -> NaiveDate {
+    start_of_week(date) + Days::new(6)
+}
+
+fn start_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("every month has a first day")
+}
+
+/// Adds `count` units (`d`, `w`, `m` or `y`) to `date`; negative counts go
+/// back. Months and years keep the day, clamped to the month's last day.
+fn shift(date: NaiveDate, count: i64, unit: &str) -> Option<NaiveDate> {
+    let magnitude = count.unsigned_abs();
+    match unit {
+        "d" | "day" | "days" => {
+            let days = Days::new(magnitude);
+            if count < 0 { date.checked_sub_days(days) } else { date.checked_add_days(days) }
+        }
+        "w" | "week" | "weeks" => shift(date, count.checked_mul(7)?, "d"),
+        "m" | "month" | "months" => {
+            let months = Months::new(u32::try_from(magnitude).ok()?);
+            if count < 0 { date.checked_sub_months(months) } else { date.checked_add_months(months) }
+        }
+        "y" | "year" | "years" => shift(date, count.checked_mul(12)?, "m"),
+        _ => None,
+    }
+}
+
+/// The day `period` after `from`, for periods such as `7d`, `2w`, `1m` or
+/// `1y`; a bare number counts days.
+pub fn period_end(period: &str, from: NaiveDate) -> Result<NaiveDate> {
+    let text = period.trim().to_lowercase();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let unit = match text[split..].trim() {
+        "" => "d",
+        unit => unit,
+    };
+    text[..split]
+        .parse::<i64>()
+        .ok()
+        .and_then(|count| shift(from, count, unit))
+        .ok_or_else(|| anyhow!("Invalid period \"{}\": use a number of days, weeks, months or years such as 7d, 2w or 1m", period.trim()))
+}
+
+/// Everything but timestamps: ISO dates, names and offsets.
+fn parse_day(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    match text {
+        "today" | "now" => return Some(today),
+        "tomorrow" | "tmr" => return today.succ_opt(),
+        "yesterday" => return today.pred_opt(),
+        "next week" => return Some(start_of_week(today) + Days::new(7)),
+        "next month" => return start_of_month(today).checked_add_months(Months::new(1)),
+        "next year" => return NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+        "end of week" | "eow" => return Some(end_of_week(today)),
+        "end of month" | "eom" => return start_of_month(today).checked_add_months(Months::new(1))?.pred_opt(),
+        "end of year" | "eoy" => return NaiveDate::from_ymd_opt(today.year(), 12, 31),
+        _ => {}
+    }
+
+    if let Some(weekday) = parse_weekday(text) {
+        return Some(next_weekday(today, weekday));
+    }
+    if let Some(weekday) = text.strip_prefix("next ").and_then(parse_weekday) {
+        return Some(next_weekday(today.succ_opt()?, weekday));
+    }
+
+    // +3d, -1w
+    if let Some(sign @ ('+' | '-')) = text.chars().next() {
+        let rest = &text[1..];
+        let split = rest.find(|c: char| !c.is_ascii_digit())?;
+        let count = parse_count(&rest[..split])?;
+        return shift(today, if sign == '-' { count.checked_neg()? } else { count }, rest[split..].trim());
+    }
+
+    // in 3 days, 2 weeks ago
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        ["in", count, unit] => shift(today, parse_count(count)?, unit),
+        [count, unit, "ago"] => shift(today, parse_count(count)?.checked_neg()?, unit),
+        _ => None,
+    }
+}
+
+/// The number of units in an offset: digits only, and small enough that
+/// negating or scaling it cannot overflow.
+fn parse_count(text: &str) -> Option<i64> {
+    if !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse::<u32>().ok().map(i64::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-01-17, 10:00 at UTC+1.
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-01-17T10:00:00+01:00").unwrap()
+    }
+
+    fn parse(input: &str) -> String {
+        parse_date_at(input, now()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_iso_dates_and_timestamps() {
+        assert_eq!(parse("2024-02-29"), "2024-02-29");
+        assert_eq!(parse("2024-01-20T14:00"), "2024-01-20T14:00:00+01:00");
+        assert_eq!(parse("2024-01-20 14:00:30"), "2024-01-20T14:00:30+01:00");
+        assert_eq!(parse("2024-01-20T14:00Z"), "2024-01-20T14:00:00+00:00");
+        assert_eq!(parse("2024-01-20T14:00:00-05:00"), "2024-01-20T14:00:00-05:00");
+
+        assert!(parse_date_at("2024-13-40", now()).is_err());
+        assert!(parse_date_at("2023-02-29", now()).is_err());
+        assert!(parse_date_at("2024-01-20T25:00", now()).is_err());
+    }
+
+    #[test]
+    fn test_named_days() {
+        assert_eq!(parse("today"), "2024-01-17");
+        assert_eq!(parse("Tomorrow"), "2024-01-18");
+        assert_eq!(parse("yesterday"), "2024-01-16");
+        assert_eq!(parse("friday"), "2024-01-19");
+        assert_eq!(parse("wed"), "2024-01-17");
+        assert_eq!(parse("next wednesday"), "2024-01-24");
+        assert_eq!(parse("next fri"), "2024-01-19");
+        assert_eq!(parse("next week"), "2024-01-22");
+        assert_eq!(parse("next month"), "2024-02-01");
+        assert_eq!(parse("end of week"), "2024-01-21");
+        assert_eq!(parse("end of month"), "2024-01-31");
+        assert_eq!(parse("eoy"), "2024-12-31");
+    }
+
+    #[test]
+    fn test_offsets_and_times() {
+        assert_eq!(parse("+3d"), "2024-01-20");
+        assert_eq!(parse("-1w"), "2024-01-10");
+        assert_eq!(parse("+1m"), "2024-02-17");
+        assert_eq!(parse("in 2 weeks"), "2024-01-31");
+        assert_eq!(parse("3 days ago"), "2024-01-14");
+        assert_eq!(parse("tomorrow 9:30"), "2024-01-18T09:30:00+01:00");
+        assert_eq!(parse("fri at 14:00"), "2024-01-19T14:00:00+01:00");
+
+        let err = parse_date_at("next blursday", now()).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid date \"next blursday\": use YYYY-MM-DD"));
+        assert!(parse_date_at("+3x", now()).is_err());
+        assert!(parse_date_at("9223372036854775808 days ago", now()).is_err());
+        assert!(parse_date_at("-9223372036854775808 days ago", now()).is_err());
+        assert!(parse_date_at("in -3 days", now()).is_err());
+        assert!(parse_date_at("+99999999999d", now()).is_err());
+        assert!(parse_date_at("", now()).is_err());
+    }
+
+    #[test]
+    fn test_periods() {
+        let today = now().date_naive();
+        assert_eq!(period_end("7d", today).unwrap().to_string(), "2024-01-24");
+        assert_eq!(period_end("2w", today).unwrap().to_string(), "2024-01-31");
+        assert_eq!(period_end("1 month", today).unwrap().to_string(), "2024-02-17");
+        assert_eq!(period_end("3", today).unwrap().to_string(), "2024-01-20");
+        assert_eq!(end_of_week(today).to_string(), "2024-01-21");
+
+        let err = period_end("soon", today).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid period \"soon\""));
+        assert!(period_end("-7d", today).is_err());
+    }
+
+    #[test]
+    fn test_due_date_ranges_and_time_zones() {
+        let due = DueDate::parse_at("2026-11-01T14:00", Some("2026-11-03"), None, now()).unwrap();
+        assert_eq!(due.to_notion(), serde_json::json!({ "start": "2026-11-01T14:00:00+01:00", "end": "2026-11-03" }));
+
+        let paris = Tz::Europe__Paris;
+        let due = DueDate::parse_at("2026-11-01T14:00", None, Some(paris), now().with_timezone(&paris)).unwrap();
+        assert_eq!(due.to_notion(), serde_json::json!({ "start": "2026-11-01T14:00:00", "time_zone": "Europe/Paris" }));
+        assert_eq!(due.start.to_string(), "2026-11-01T14:00:00+01:00");
+
+        let due = DueDate::parse_at("tomorrow", None, Some(paris), now().with_timezone(&paris)).unwrap();
+        assert_eq!(due.time_zone, None);
+
+        let err = DueDate::parse_at("2026-11-03", Some("2026-11-01"), None, now()).unwrap_err();
+        assert_eq!(err.to_string(), "The end date 2026-11-01 is before the start date 2026-11-03");
+        assert!(parse_time_zone("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn test_due_date_from_notion_and_display() {
+        let timed = serde_json::json!({
+            "start": "2026-11-01T14:00:00.000+01:00",
+            "end": "2026-11-01T16:00:00.000+01:00",
+            "time_zone": null
+        });
+        let due = DueDate::from_notion(&timed).unwrap();
+        assert!(!due.is_all_day());
+        assert_eq!(due.format_in(&now().timezone()), "2026-11-01 14:00 → 16:00");
+        assert_eq!(due.format_in(&Utc), "2026-11-01 13:00 → 15:00");
+
+        let zoned = serde_json::json!({ "start": "2026-11-01T14:00:00.000", "time_zone": "America/New_York" });
+        let due = DueDate::from_notion(&zoned).unwrap();
+        assert_eq!(due.start.to_string(), "2026-11-01T14:00:00-05:00");
+        assert_eq!(due.to_notion(), serde_json::json!({ "start": "2026-11-01T14:00:00", "time_zone": "America/New_York" }));
+
+        let days = DueDate::from_notion(&serde_json::json!({ "start": "2026-11-01", "end": "2026-11-03" })).unwrap();
+        assert!(days.is_all_day());
+        assert_eq!(days.format_in(&Utc), "2026-11-01 → 2026-11-03");
+        assert_eq!(serde_json::to_value(days).unwrap(), "2026-11-01/2026-11-03");
+        assert_eq!(serde_json::from_value::<DueDate>("2026-11-01/2026-11-03".into()).unwrap(), days);
+
+        assert_eq!(DueDate::from_notion(&serde_json::Value::Null), None);
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::notion::Task;
+
+/// A request that a dry-run client built but did not send.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlannedRequest {
+    pub method: String,
+    pub url: String,
+    pub body: Option<serde_json::Value>,
+    /// The task before the request; `None` when it creates one.
+    pub before: Option<Task>,
+    /// The task as the request would leave it; `None` when it deletes one.
+    pub after: Option<Task>,
+}
+
+impl PlannedRequest {
+    /// The task fields the request would change.
+    pub fn changes(&self) -> Vec<FieldChange> {
+        match (&self.before, &self.after) {
+            (Some(before), Some(after)) => changes(before, after),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for PlannedRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+        if let Some(body) = &self.body {
+            let body = serde_json::to_string_pretty(body).map_err(|_| fmt::Error)?;
+            write!(f, "\n{}", body)?;
+        }
+        Ok(())
+    }
+}
+
+/// One field of a task that differs between two versions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+/// The user-visible fields that differ between `before` and `after`, as
+/// text; absent values are empty.
+pub fn changes(before: &Task, after: &Task) -> Vec<FieldChange> {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    let fields = [
+        ("title", before.title.clone(), after.title.clone()),
+        ("status", before.status.to_string(), after.status.to_string()),
+        (
+            "priority",
+            before.priority.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+            after.priority.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        ),
+        (
+            "due",
+            before.due_date.map(|d| d.to_string()).unwrap_or_default(),
+            after.due_date.map(|d| d.to_string()).unwrap_or_default(),
+        ),
+        ("tags", before.tags.join(", "), after.tags.join(", ")),
+        ("description", text(&before.description), text(&after.description)),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange { field, before, after })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notion::{TaskPriority, TaskStatus, TaskUpdate};
+
+    fn task() -> Task {
+        Task {
+            status_color: Some("gray".to_string()),
+            due_date: "2024-01-20".parse().ok(),
+            tags: vec!["dev".to_string()],
+            ..Task::for_test("Ship")
+        }
+    }
+
+    #[test]
+    fn test_changes_list_only_modified_fields() {
+        let update = TaskUpdate::new().status(TaskStatus::Done).priority(TaskPriority::High).clear_due_date();
+        let after = update.apply_to(&task());
+
+        assert_eq!(after.status_color, None);
+        assert_eq!(
+            changes(&task(), &after),
+            vec![
+                FieldChange { field: "status", before: "Not started".to_string(), after: "Done".to_string() },
+                FieldChange { field: "priority", before: String::new(), after: "High".to_string() },
+                FieldChange { field: "due", before: "2024-01-20".to_string(), after: String::new() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_planned_request_display() {
+        let request = PlannedRequest {
+            method: "PATCH".to_string(),
+            url: "https://api.notion.com/v1/pages/abc".to_string(),
+            body: Some(serde_json::json!({ "archived": true })),
+            before: Some(task()),
+            after: None,
+        };
+
+        assert_eq!(request.to_string(), "PATCH https://api.notion.com/v1/pages/abc\n{\n  \"archived\": true\n}");
+        assert!(request.changes().is_empty());
+    }
+}
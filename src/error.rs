@@ -0,0 +1,154 @@
+use std::fmt;
+use std::time::Duration;
+
+/// An error response returned by the Notion API.
+///
+/// Notion reports failures as `{"object": "error", "status", "code", "message"}`;
+/// the `code` selects the variant and the `message` is kept as sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotionError {
+    /// The token is missing, malformed or revoked (`unauthorized`).
+    Unauthorized { message: String },
+    /// The page or database does not exist or is not shared with the
+    /// integration (`object_not_found`).
+    ObjectNotFound { message: String },
+    /// The request body did not match the database schema (`validation_error`).
+    ValidationError { message: String },
+    /// Too many requests were sent (`rate_limited`). `retry_after` comes from
+    /// the `Retry-After` header when Notion sends one.
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The page was changed by another request at the same time (`conflict_error`).
+    Conflict { message: String },
+    /// Notion failed to handle the request (any 5xx status).
+    ServerError {
+        status: u16,
+        code: String,
+        message: String,
+    },
+    /// Any other client error, such as `restricted_resource` or `invalid_request`.
+    Api {
+        status: u16,
+        code: String,
+        message: String,
+    },
+}
+
+impl NotionError {
+    /// Builds an error from an HTTP status and the raw response body.
+    pub fn from_response(status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        let parsed = serde_json::from_str::<serde_json::Value>(body).ok();
+        let code = parsed
+            .as_ref()
+            .and_then(|v| v["code"].as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| default_code(status).to_string());
+        let message = parsed
+            .as_ref()
+            .and_then(|v| v["message"].as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("HTTP status {}", status));
+
+        match code.as_str() {
+            "unauthorized" => NotionError::Unauthorized { message },
+            "object_not_found" => NotionError::ObjectNotFound { message },
+            "validation_error" => NotionError::ValidationError { message },
+            "rate_limited" => NotionError::RateLimited { message, retry_after },
+            "conflict_error" => NotionError::Conflict { message },
+            _ if status >= 500 => NotionError::ServerError { status, code, message },
+            _ => NotionError::Api { status, code, message },
+        }
+    }
+
+    /// Whether sending the same request again may succeed: rate limits and
+    /// server errors are transient, everything else is not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, NotionError::RateLimited { .. } | NotionError::ServerError { .. })
+    }
+
+    /// The Notion error code, e.g. `object_not_found`.
+    pub fn code(&self) -> &str {
+        match self {
+            NotionError::Unauthorized { .. } => "unauthorized",
+            NotionError::ObjectNotFound { .. } => "object_not_found",
+            NotionError::ValidationError { .. } => "validation_error",
+            NotionError::RateLimited { .. } => "rate_limited",
+            NotionError::Conflict { .. } => "conflict_error",
+            NotionError::ServerError { code, .. } | NotionError::Api { code, .. } => code,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            NotionError::Unauthorized { message }
+            | NotionError::ObjectNotFound { message }
+            | NotionError::ValidationError { message }
+            | NotionError::RateLimited { message, .. }
+            | NotionError::Conflict { message }
+            | NotionError::ServerError { message, .. }
+            | NotionError::Api { message, .. } => message,
+        }
+    }
+}
+
+fn default_code(status: u16) -> &'static str {
+    match status {
+        400 => "invalid_request",
+        401 => "unauthorized",
+        403 => "restricted_resource",
+        404 => "object_not_found",
+        409 => "conflict_error",
+        429 => "rate_limited",
+        s if s >= 500 => "internal_server_error",
+        _ => "unknown_error",
+    }
+}
+
+impl fmt::Display for NotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Notion API error ({}): {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for NotionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_object_not_found() {
+        let body = r#"{"object":"error","status":404,"code":"object_not_found","message":"Could not find page with ID: abc."}"#;
+        let error = NotionError::from_response(404, body, None);
+
+        assert_eq!(error, NotionError::ObjectNotFound {
+            message: "Could not find page with ID: abc.".to_string(),
+        });
+        assert_eq!(error.code(), "object_not_found");
+    }
+
+    #[test]
+    fn test_parse_server_error_code() {
+        let body = r#"{"object":"error","status":503,"code":"service_unavailable","message":"Notion is unavailable."}"#;
+        let error = NotionError::from_response(503, body, None);
+
+        assert_eq!(error.code(), "service_unavailable");
+        assert!(matches!(error, NotionError::ServerError { status: 503, .. }));
+    }
+
+    #[test]
+    fn test_unparsable_body_falls_back_to_status() {
+        let error = NotionError::from_response(401, "<html>nope</html>", None);
+
+        assert!(matches!(error, NotionError::Unauthorized { .. }));
+        assert_eq!(error.message(), "HTTP status 401");
+    }
+
+    #[test]
+    fn test_display_includes_code_and_message() {
+        let error = NotionError::ValidationError { message: "Status is not a property.".to_string() };
+        assert_eq!(error.to_string(), "Notion API error (validation_error): Status is not a property.");
+    }
+}
@@ -1,7 +1,30 @@
+pub mod agenda;
+pub mod bulk;
 pub mod config;
+pub mod dates;
+pub mod dry_run;
+pub mod error;
 pub mod notion;
+pub mod output;
+pub mod resolve;
+pub mod retry;
+pub mod secrets;
+pub mod sort;
+pub mod table;
+pub mod tags;
+pub mod template;
 
-pub use config::Config;
-pub use notion::{NotionClient, Task, TaskStatus, TaskPriority};
+pub use config::{Config, ConfigFile, Defaults, Profile, PropertyKind, PropertyMap, PropertyRef};
+pub use error::NotionError;
+pub use notion::{
+    parse_tags, property_to_string, BotUser, DatabaseSchema, DateCondition, NewTask, NotionClient,
+    PropertySchema, SelectOption, SortDirection, SortProperty, StatusGroup, Task, TaskDetails,
+    TaskFilter, TaskPage, TaskPriority, TaskQuery, TaskSort, TaskStatus, TaskUpdate,
+};
+
+pub use output::{OutputFormat, TaskWriter};
+pub use retry::{RateLimiter, RetryPolicy};
+pub use secrets::TokenSource;
+pub use template::Template;
 
 pub type Result<T> = anyhow::Result<T>; 
\ No newline at end of file
@@ -1,14 +1,40 @@
-mod config;
-mod notion;
-
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use colored::Colorize;
-use notion_cli_rs::{Config, NotionClient, TaskStatus, TaskPriority};
+use notion_cli_rs::{
+    parse_tags, Config, ConfigFile, DatabaseSchema, DateCondition, NewTask, NotionClient, NotionError, Profile,
+    PropertyKind, PropertyMap, PropertyRef, SortDirection, SortProperty, TaskFilter, TaskQuery,
+    OutputFormat, Task, TaskSort, TaskUpdate, TaskWriter, Template, TokenSource,
+};
+use notion_cli_rs::agenda::{self, Bucket};
+use notion_cli_rs::bulk::{self, BulkAction};
+use notion_cli_rs::dates::{self, DueDate};
+use notion_cli_rs::dry_run::PlannedRequest;
+use notion_cli_rs::tags::{self, TagUsage};
+use notion_cli_rs::output::write_task;
+use notion_cli_rs::resolve::{ambiguous_title, find_by_title, resolve_task_id, AliasCache};
+use notion_cli_rs::secrets;
+use notion_cli_rs::sort;
+use notion_cli_rs::table::{
+    notion_color, render_tasks, render_tasks_from, terminal_width, Column, DEFAULT_COLUMNS,
+};
+use notion_cli_rs::template::{self, DEFAULT_TEMPLATE};
+use std::io::{self, BufRead, IsTerminal, Write};
 use anyhow::Result;
+use futures::{future, stream, StreamExt, TryStreamExt};
+
+const TASK_ID_HELP: &str = "Task number shown by `list`, ID prefix, full ID or Notion URL";
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    #[arg(long, global = true, help = "Config file profile to use (defaults to $NOTION_PROFILE)")]
+    profile: Option<String>,
+    #[arg(short, long, global = true, default_value_t = OutputFormat::Text, help = "Task output format: text, json, ndjson, yaml, csv or tsv")]
+    output: OutputFormat,
+    #[arg(long, global = true, value_name = "TEMPLATE", help = "Print tasks with a template, e.g. \"{status_symbol} {title} ({due})\"")]
+    format: Option<String>,
+    #[arg(long, global = true, help = "Show the requests that would change Notion, with a diff of each task, without sending them")]
+    dry_run: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -19,10 +45,16 @@ enum Commands {
     Add {
         #[arg(help = "Task title")]
         title: String,
-        #[arg(short, long, help = "Task priority (High, Medium, Low)")]
+        #[arg(short, long, help = "Task status (any status option of the database)")]
+        status: Option<String>,
+        #[arg(short, long, help = "Task priority (any priority option of the database)")]
         priority: Option<String>,
-        #[arg(short, long, help = "Task due date (YYYY-MM-DD)")]
+        #[arg(short, long, help = "Task due date (YYYY-MM-DD, YYYY-MM-DDTHH:MM, tomorrow, next friday, +3d...)")]
         due_date: Option<String>,
+        #[arg(long, value_name = "DATE", requires = "due_date", help = "End of the due date range")]
+        until: Option<String>,
+        #[arg(long, value_name = "ZONE", requires = "due_date", help = "Time zone of due times, e.g. Europe/Paris (default: local)")]
+        tz: Option<String>,
         #[arg(short, long, help = "Task tags (comma-separated)")]
         tags: Option<String>,
         #[arg(short = 'D', long, help = "Task description")]
@@ -30,190 +62,1502 @@ enum Commands {
     },
     #[command(about = "List all tasks")]
     List {
-        #[arg(short, long, help = "Filter by status (Not started, In progress, Done)")]
-        status: Option<String>,
-        #[arg(short, long, help = "Filter by priority (High, Medium, Low)")]
-        priority: Option<String>,
-        #[arg(short, long, help = "Filter by tag")]
-        tag: Option<String>,
-        #[arg(short = 'S', long, help = "Sort by due date")]
+        #[command(flatten)]
+        filters: FilterArgs,
+        #[arg(short = 'S', long, conflicts_with = "sort", help = "Sort by due date (same as --sort due)")]
         sort_by_due_date: bool,
+        #[arg(long, value_name = "KEYS", value_delimiter = ',', allow_hyphen_values = true, help = "Sort keys (comma-separated), each ascending unless written key:desc or -key, e.g. priority:desc,-due,title; keys: title, status, priority, due")]
+        sort: Option<Vec<TaskSort>>,
+        #[arg(long, help = "Number of tasks fetched per request (1-100)")]
+        page_size: Option<u32>,
+        #[arg(short, long, value_delimiter = ',', help = "Table columns (comma-separated): num, id, title, status, priority, due, tags, description, url, created, edited")]
+        columns: Option<Vec<Column>>,
+        #[arg(short, long, conflicts_with = "columns", help = "Print every task as a block of fields instead of a table")]
+        long: bool,
+    },
+    #[command(about = "Show open tasks grouped into Overdue, Today, This week, Later and No date")]
+    Agenda {
+        #[command(flatten)]
+        filters: FilterArgs,
     },
-    #[command(about = "Update task status")]
+    #[command(about = "Show every detail of a task")]
+    Show {
+        #[command(flatten)]
+        task: TaskArg,
+    },
+    #[command(about = "Show the database properties and how task fields map to them")]
+    Schema,
+    #[command(about = "Set up a config file profile interactively")]
+    Init,
+    #[command(about = "Change or delete every task matching filters", subcommand)]
+    Bulk(BulkCommand),
+    #[command(about = "Store, remove or check the Notion token", subcommand)]
+    Auth(AuthCommand),
+    #[command(about = "List the profiles of the config file")]
+    Profiles,
+    #[command(about = "Update task status", allow_missing_positional = true)]
     Status {
-        #[arg(help = "Task ID")]
-        id: String,
-        #[arg(help = "New status (Not started, In progress, Done)")]
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "New status (any status option of the database)")]
         status: String,
     },
+    #[command(about = "Update several fields of a task at once")]
+    Edit {
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(long, help = "New title")]
+        title: Option<String>,
+        #[arg(short, long, help = "New status (any status option of the database)")]
+        status: Option<String>,
+        #[arg(short, long, conflicts_with = "clear_priority", help = "New priority (any priority option of the database)")]
+        priority: Option<String>,
+        #[arg(long, help = "Remove the priority")]
+        clear_priority: bool,
+        #[arg(short, long, conflicts_with = "no_due", help = "New due date (YYYY-MM-DD, YYYY-MM-DDTHH:MM, tomorrow, next friday, +3d...)")]
+        due: Option<String>,
+        #[arg(long, value_name = "DATE", requires = "due", help = "End of the due date range")]
+        until: Option<String>,
+        #[arg(long, value_name = "ZONE", requires = "due", help = "Time zone of due times, e.g. Europe/Paris (default: local)")]
+        tz: Option<String>,
+        #[arg(long, help = "Remove the due date")]
+        no_due: bool,
+        #[arg(short, long, conflicts_with = "clear_tags", help = "Replace tags (comma-separated)")]
+        tags: Option<String>,
+        #[arg(long, help = "Remove every tag")]
+        clear_tags: bool,
+        #[arg(short = 'D', long, conflicts_with = "clear_description", help = "New description")]
+        description: Option<String>,
+        #[arg(long, help = "Remove the description")]
+        clear_description: bool,
+    },
     #[command(about = "Delete a task")]
     Delete {
-        #[arg(help = "Task ID")]
-        id: String,
+        #[command(flatten)]
+        task: TaskArg,
     },
-    #[command(about = "Set task priority")]
+    #[command(about = "Set task priority", allow_missing_positional = true)]
     Priority {
-        #[arg(help = "Task ID")]
-        id: String,
-        #[arg(help = "Priority (High, Medium, Low)")]
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "Priority (any priority option of the database)")]
         priority: String,
     },
-    #[command(about = "Set task due date")]
+    #[command(about = "Set task due date", allow_missing_positional = true)]
     DueDate {
-        #[arg(help = "Task ID")]
-        id: String,
-        #[arg(help = "Due date (YYYY-MM-DD)")]
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "Due date (YYYY-MM-DD, YYYY-MM-DDTHH:MM, tomorrow, next friday, end of month, +3d...)")]
         date: String,
+        #[arg(long, value_name = "DATE", help = "End of the due date range")]
+        until: Option<String>,
+        #[arg(long, value_name = "ZONE", help = "Time zone of due times, e.g. Europe/Paris (default: local)")]
+        tz: Option<String>,
     },
-    #[command(about = "Add tags to a task")]
+    #[command(
+        about = "Add, remove, set or list tags; `tags <ID> <TAGS>` is short for `tags add`",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true,
+        allow_missing_positional = true
+    )]
     Tags {
-        #[arg(help = "Task ID")]
-        id: String,
-        #[arg(help = "Tags (comma-separated)")]
-        tags: String,
+        #[command(subcommand)]
+        command: Option<TagsCommand>,
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(required = true, help = "Tags to add (comma-separated)")]
+        tags: Option<String>,
     },
-    #[command(about = "Set task description")]
+    #[command(about = "Set task description", allow_missing_positional = true)]
     Description {
-        #[arg(help = "Task ID")]
-        id: String,
+        #[command(flatten)]
+        task: TaskArg,
         #[arg(help = "Description")]
         description: String,
     },
 }
 
+/// Filters selecting tasks, shared by `list` and the bulk commands.
+#[derive(Args)]
+struct FilterArgs {
+    #[arg(short, long, help = "Filter by status or status group (To-do, In progress, Complete)")]
+    status: Option<String>,
+    #[arg(short, long, help = "Filter by priority (any priority option of the database)")]
+    priority: Option<String>,
+    #[arg(short, long, help = "Filter by tag (exact tag name)")]
+    tag: Option<String>,
+    #[arg(short = 'n', long, help = "Filter by text contained in the title")]
+    name: Option<String>,
+    #[arg(long, value_name = "DATE", value_parser = parse_due, help = "Only tasks due before DATE (YYYY-MM-DD, today, +7d...)")]
+    due_before: Option<String>,
+    #[arg(long, value_name = "DATE", value_parser = parse_due, help = "Only tasks due after DATE (YYYY-MM-DD, today, -7d...)")]
+    due_after: Option<String>,
+    #[arg(long, conflicts_with_all = ["due_today", "due_within"], help = "Only open tasks due before today")]
+    overdue: bool,
+    #[arg(long, conflicts_with = "due_within", help = "Only tasks due today")]
+    due_today: bool,
+    #[arg(long, value_name = "PERIOD", value_parser = parse_period, help = "Only tasks due from today to the end of PERIOD, e.g. 7d, 2w or 1m")]
+    due_within: Option<String>,
+}
+
+impl FilterArgs {
+    fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.priority.is_none()
+            && self.tag.is_none()
+            && self.name.is_none()
+            && self.due_before.is_none()
+            && self.due_after.is_none()
+            && !self.overdue
+            && !self.due_today
+            && self.due_within.is_none()
+    }
+
+    /// A query with every given filter, sent to Notion, and only open
+    /// tasks when `open` is set. Notion compares the start of date ranges,
+    /// so the due date flags also need [`FilterArgs::keeps`] on the tasks.
+    async fn query(&self, client: &NotionClient, open: bool) -> Result<TaskQuery> {
+        let mut query = TaskQuery::new();
+
+        if let Some(s) = &self.status {
+            query = query.filter(client.status_filter(s).await?);
+        }
+
+        if let Some(p) = &self.priority {
+            query = query.filter(TaskFilter::PriorityEquals(client.parse_priority(p).await?));
+        }
+
+        if let Some(tag) = &self.tag {
+            query = query.filter(TaskFilter::TagContains(tag.clone()));
+        }
+
+        if let Some(name) = &self.name {
+            query = query.filter(TaskFilter::NameContains(name.clone()));
+        }
+
+        if let Some(date) = &self.due_before {
+            query = query.filter(TaskFilter::Due(DateCondition::Before(date.clone())));
+        }
+
+        if let Some(date) = &self.due_after {
+            query = query.filter(TaskFilter::Due(DateCondition::After(date.clone())));
+        }
+
+        if open || self.overdue {
+            query = query.filter(client.open_filter().await?);
+        }
+
+        let today = chrono::Local::now().date_naive().to_string();
+        if self.overdue {
+            query = query.filter(TaskFilter::Due(DateCondition::Before(today.clone())));
+        }
+
+        if self.due_today {
+            query = query.filter(TaskFilter::Due(DateCondition::OnOrBefore(today.clone())));
+        }
+
+        if let Some(end) = &self.due_within {
+            query = query
+                .filter(TaskFilter::Due(DateCondition::OnOrAfter(today)))
+                .filter(TaskFilter::Due(DateCondition::OnOrBefore(end.clone())));
+        }
+
+        Ok(query)
+    }
+
+    /// Whether `task` matches `--overdue` and `--due-today` on `today` by
+    /// the end of its date range, as the agenda buckets do.
+    fn keeps(&self, task: &Task, today: chrono::NaiveDate) -> bool {
+        (!self.overdue || task.is_overdue(today)) && (!self.due_today || task.is_due_on(today))
+    }
+}
+
+/// Turns a date given on the command line, such as `tomorrow` or `+3d`,
+/// into the ISO form sent to Notion.
+fn parse_due(input: &str) -> Result<String> {
+    dates::parse_date(input).map(|date| date.to_string())
+}
+
+/// The last day of a period such as `7d` starting today.
+fn parse_period(input: &str) -> Result<String> {
+    dates::period_end(input, chrono::Local::now().date_naive()).map(|date| date.to_string())
+}
+
+#[derive(Subcommand)]
+enum BulkCommand {
+    #[command(about = "Set the status of every matching task")]
+    Status {
+        #[arg(help = "New status (any status option of the database)")]
+        new_status: String,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    #[command(about = "Set the priority of every matching task")]
+    Priority {
+        #[arg(help = "New priority (any priority option of the database)")]
+        new_priority: String,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    #[command(about = "Add tags to every matching task")]
+    Tags {
+        #[arg(help = "Tags to add (comma-separated)")]
+        tags: String,
+        #[command(flatten)]
+        selection: Selection,
+    },
+    #[command(about = "Delete every matching task")]
+    Delete {
+        #[command(flatten)]
+        selection: Selection,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagsCommand {
+    #[command(about = "Add tags to a task, keeping the ones it has", allow_missing_positional = true)]
+    Add {
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "Tags to add (comma-separated)")]
+        tags: String,
+    },
+    #[command(about = "Remove tags from a task", visible_alias = "remove", allow_missing_positional = true)]
+    Rm {
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "Tags to remove (comma-separated)")]
+        tags: String,
+    },
+    #[command(about = "Replace every tag of a task", allow_missing_positional = true)]
+    Set {
+        #[command(flatten)]
+        task: TaskArg,
+        #[arg(help = "New tags (comma-separated); \"\" removes every tag")]
+        tags: String,
+    },
+    #[command(about = "List the tag options of the database and how many tasks use each")]
+    List,
+    #[command(about = "Rename a tag option of the database, on every task that has it")]
+    Rename {
+        #[arg(help = "Current tag name")]
+        from: String,
+        #[arg(help = "New tag name")]
+        to: String,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+    #[command(about = "Replace several tags with one on every task, then remove them from the database")]
+    Merge {
+        #[arg(required = true, help = "Tags to merge")]
+        from: Vec<String>,
+        #[arg(long, help = "Tag they are merged into")]
+        into: String,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+}
+
+/// The tasks a bulk command changes and how to confirm it.
+#[derive(Args)]
+struct Selection {
+    #[command(flatten)]
+    filters: FilterArgs,
+    #[arg(long, help = "Change every task of the database when no filter is given")]
+    all: bool,
+    #[arg(short, long, help = "Do not ask for confirmation")]
+    yes: bool,
+}
+
+/// The task a command works on: an ID-like argument or `--match`.
+#[derive(Args)]
+struct TaskArg {
+    #[arg(help = TASK_ID_HELP, required_unless_present = "title_match", conflicts_with = "title_match")]
+    id: Option<String>,
+    #[arg(short = 'm', long = "match", value_name = "TEXT", help = "Pick the task by title instead of ID (asks when several match)")]
+    title_match: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    #[command(about = "Check a token and store it in the system keyring")]
+    Login {
+        #[arg(long, help = "Store the token in a passphrase-encrypted file instead of the keyring")]
+        file: bool,
+    },
+    #[command(about = "Remove the stored token")]
+    Logout,
+    #[command(about = "Show where the token is read from and check it")]
+    Status,
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     dotenv::dotenv().ok();
     env_logger::init();
 
     let cli = Cli::parse();
 
-    let config = Config {
-        notion_token: std::env::var("NOTION_TOKEN")?,
-        database_id: std::env::var("NOTION_DATABASE_ID")?,
+    if let Err(err) = run(cli).await {
+        print_error(&err);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Commands::Init => return init(cli.profile.as_deref(), cli.dry_run).await,
+        Commands::Auth(command) => return auth(&command, cli.profile.as_deref(), cli.dry_run).await,
+        // Only reads the config file, so a dry run changes nothing.
+        Commands::Profiles => return print_profiles(),
+        _ => {}
+    }
+
+    let config = Config::load(cli.profile.as_deref())?;
+    let defaults = config.defaults.clone();
+    let account = config.account().to_string();
+
+    if cli.format.is_some() && cli.output.is_machine() {
+        anyhow::bail!("--format cannot be combined with --output {}", cli.output);
+    }
+    // A profile template only applies to text output.
+    let template = match cli.format.as_ref().or(defaults.format.as_ref()) {
+        Some(format) if !cli.output.is_machine() => Some(Template::parse(format)?),
+        _ => None,
     };
 
+    if cli.dry_run && matches!(cli.output, OutputFormat::Csv | OutputFormat::Tsv) {
+        anyhow::bail!("--dry-run prints requests as text, json, ndjson or yaml, not {}", cli.output);
+    }
+
     let client = NotionClient::new(config)?;
+    let client = if cli.dry_run { client.with_dry_run() } else { client };
 
     match &cli.command {
-        Commands::Add { title, priority, due_date, tags, description } => {
-            let mut task = client.add_task(title).await?;
+        Commands::Add { title, status, priority, due_date, until, tz, tags, description } => {
+            let mut new_task = NewTask::new(title.clone());
 
-            if let Some(p) = priority {
-                let priority = p.parse::<TaskPriority>()?;
-                task = client.set_task_priority(&task.id, priority).await?;
+            if let Some(s) = status.as_ref().or(defaults.status.as_ref()) {
+                new_task = new_task.status(client.parse_status(s).await?);
+            }
+
+            if let Some(p) = priority.as_ref().or(defaults.priority.as_ref()) {
+                new_task = new_task.priority(client.parse_priority(p).await?);
             }
 
             if let Some(d) = due_date {
-                task = client.set_task_due_date(&task.id, d).await?;
+                new_task = new_task.due_date(DueDate::parse(d, until.as_deref(), tz.as_deref())?);
             }
 
             if let Some(t) = tags {
-                task = client.add_task_tags(&task.id, t).await?;
+                new_task = new_task.tags(parse_tags(t));
+            } else if !defaults.tags.is_empty() {
+                new_task = new_task.tags(defaults.tags.clone());
             }
 
             if let Some(d) = description {
-                task = client.set_task_description(&task.id, d).await?;
+                new_task = new_task.description(d.clone());
+            }
+
+            let task = client.create_task(&new_task).await?;
+
+            report(&client, cli.output, template.as_ref(), "Task added successfully!", &task)?;
+        }
+        Commands::List { filters, sort_by_due_date, sort, page_size, columns, long } => {
+            let mut query = filters.query(&client, false).await?;
+
+            let sorts = match sort {
+                Some(sorts) => sorts.clone(),
+                None if *sort_by_due_date => {
+                    vec![TaskSort { property: SortProperty::DueDate, direction: SortDirection::Ascending }]
+                }
+                None => Vec::new(),
+            };
+            let sort_locally = !sort::notion_can_sort(&sorts);
+            if !sort_locally {
+                for key in &sorts {
+                    query = query.sort(key.property, key.direction);
+                }
+            }
+
+            let client = match page_size.or(defaults.page_size) {
+                Some(size) => client.with_page_size(size),
+                None => client,
+            };
+
+            // Text output marks overdue tasks, which needs the status groups
+            // of the schema.
+            if !cli.output.is_machine() {
+                client.schema().await?;
+            }
+
+            // When Notion can sort, each task is printed as soon as its page
+            // arrives; otherwise every task is fetched and sorted first.
+            let today = chrono::Local::now().date_naive();
+            let matching = client.stream_query(query).try_filter(|task| future::ready(filters.keeps(task, today)));
+            let mut tasks = if sort_locally {
+                let status_order = sort::status_order(client.schema().await?, client.property_map());
+                let mut fetched = matching.try_collect::<Vec<_>>().await?;
+                sort::sort_tasks(&mut fetched, &sorts, &status_order);
+                stream::iter(fetched.into_iter().map(Ok)).boxed_local()
+            } else {
+                matching.boxed_local()
+            };
+
+            if cli.output.is_machine() {
+                let mut writer = TaskWriter::new(cli.output, io::stdout().lock())?;
+                while let Some(task) = tasks.try_next().await? {
+                    writer.write(&task)?;
+                }
+                return writer.finish();
             }
 
-            println!("Task added successfully!");
-            print_task(&task);
+            // --long and --columns override a profile template, not --format.
+            let list_template = match template.as_ref() {
+                Some(template) if cli.format.is_some() || !(*long || columns.is_some()) => Some(template),
+                _ => None,
+            };
+            // Tasks are numbered in the order they are printed.
+            let mut printed = Vec::new();
+            if let Some(template) = list_template {
+                while let Some(task) = tasks.try_next().await? {
+                    println!("{}", template.render(&task));
+                    printed.push(task);
+                }
+            } else if *long {
+                while let Some(task) = tasks.try_next().await? {
+                    print_task(&task);
+                    println!();
+                    printed.push(task);
+                }
+                if printed.is_empty() {
+                    println!("No tasks found.");
+                }
+            } else {
+                // Column widths depend on every row, so the table waits for all pages.
+                printed = tasks.try_collect::<Vec<_>>().await?;
+                if printed.is_empty() {
+                    println!("No tasks found.");
+                } else {
+                    let columns = columns.as_deref().unwrap_or(&DEFAULT_COLUMNS);
+                    print!("{}", render_tasks(&printed, columns, terminal_width()));
+                }
+            }
+            save_aliases(&client, &account, &printed);
+            // `client` was consumed by `with_page_size`; listing changes nothing.
+            return Ok(());
+        }
+        Commands::Agenda { filters } => {
+            let query = filters.query(&client, true).await?.sort(SortProperty::DueDate, SortDirection::Ascending);
+            let today = chrono::Local::now().date_naive();
+            let tasks = client
+                .stream_query(query)
+                .try_filter(|task| future::ready(filters.keeps(task, today)))
+                .try_collect::<Vec<_>>()
+                .await?;
+            let groups = agenda::group(tasks, today);
+            print_agenda(cli.output, template.as_ref(), &groups)?;
+            if !cli.output.is_machine() {
+                let printed = groups.into_iter().flat_map(|group| group.tasks).collect::<Vec<_>>();
+                save_aliases(&client, &account, &printed);
+            }
+        }
+        Commands::Show { task } => {
+            let id = &task_id(&client, &account, task).await?;
+            if cli.output.is_machine() {
+                let task = client.get_task(id).await?;
+                return write_task(cli.output, &task, io::stdout().lock());
+            }
+            // For the status group, to tell whether the task is overdue.
+            client.schema().await?;
+            if let Some(template) = &template {
+                let task = client.get_task(id).await?;
+                println!("{}", template.render(&task));
+                return Ok(());
+            }
+            let details = client.get_task_details(id).await?;
+            print_task_details(&details);
+        }
+        Commands::Bulk(command) => {
+            let (action, selection) = match command {
+                BulkCommand::Status { new_status, selection } => {
+                    (BulkAction::Status(client.parse_status(new_status).await?), selection)
+                }
+                BulkCommand::Priority { new_priority, selection } => {
+                    (BulkAction::Priority(client.parse_priority(new_priority).await?), selection)
+                }
+                BulkCommand::Tags { tags, selection } => (BulkAction::AddTags(parse_tags(tags)), selection),
+                BulkCommand::Delete { selection } => (BulkAction::Delete, selection),
+            };
+            if selection.filters.is_empty() && !selection.all {
+                anyhow::bail!("No filter given: pass filters such as --status or --tag, or --all to change every task");
+            }
+            let query = selection.filters.query(&client, false).await?;
+            let today = chrono::Local::now().date_naive();
+            bulk(&client, cli.output, &action, query, |task| selection.filters.keeps(task, today), selection.yes).await?;
+        }
+        Commands::Init | Commands::Auth(_) | Commands::Profiles => unreachable!("handled before loading the configuration"),
+        Commands::Schema => {
+            let schema = client.get_database().await?;
+            print_schema(&schema, client.property_map());
+        }
+        Commands::Status { task, status } => {
+            let id = &task_id(&client, &account, task).await?;
+            let status = client.parse_status(status).await?;
+            let task = client.update_task_status(id, status).await?;
+            report(&client, cli.output, template.as_ref(), "Task status updated successfully!", &task)?;
         }
-        Commands::List { status, priority, tag, sort_by_due_date } => {
-            let mut tasks = client.list_tasks().await?;
+        Commands::Edit {
+            task,
+            title,
+            status,
+            priority,
+            clear_priority,
+            due,
+            until,
+            tz,
+            no_due,
+            tags,
+            clear_tags,
+            description,
+            clear_description,
+        } => {
+            let mut update = TaskUpdate::new();
+
+            if let Some(t) = title {
+                update = update.title(t.clone());
+            }
 
             if let Some(s) = status {
-                let status = s.parse::<TaskStatus>()?;
-                tasks.retain(|t| t.status == status);
+                update = update.status(client.parse_status(s).await?);
             }
 
             if let Some(p) = priority {
-                let priority = p.parse::<TaskPriority>()?;
-                tasks.retain(|t| t.priority.as_ref().map_or(false, |tp| *tp == priority));
+                update = update.priority(client.parse_priority(p).await?);
+            } else if *clear_priority {
+                update = update.clear_priority();
             }
 
-            if let Some(tag) = tag {
-                tasks.retain(|task| task.tags.iter().any(|t| t.to_lowercase().contains(&tag.to_lowercase())));
+            if let Some(d) = due {
+                update = update.due_date(DueDate::parse(d, until.as_deref(), tz.as_deref())?);
+            } else if *no_due {
+                update = update.clear_due_date();
             }
 
-            if *sort_by_due_date {
-                tasks.sort_by(|a, b| a.due_date.cmp(&b.due_date));
+            if let Some(t) = tags {
+                update = update.tags(parse_tags(t));
+            } else if *clear_tags {
+                update = update.clear_tags();
             }
 
-            if tasks.is_empty() {
-                println!("No tasks found.");
-                return Ok(());
+            if let Some(d) = description {
+                update = update.description(d.clone());
+            } else if *clear_description {
+                update = update.clear_description();
             }
 
-            for task in tasks {
-                print_task(&task);
-                println!();
+            if update.is_empty() {
+                anyhow::bail!("Nothing to update: pass at least one field to change (see `edit --help`)");
             }
+
+            let id = &task_id(&client, &account, task).await?;
+            let task = client.update_task(id, &update).await?;
+            report(&client, cli.output, template.as_ref(), "Task updated successfully!", &task)?;
         }
-        Commands::Status { id, status } => {
-            let status = status.parse::<TaskStatus>()?;
-            let task = client.update_task_status(id, status).await?;
-            println!("Task status updated successfully!");
-            print_task(&task);
-        }
-        Commands::Delete { id } => {
+        Commands::Delete { task } => {
+            let id = &task_id(&client, &account, task).await?;
             client.delete_task(id).await?;
-            println!("Task deleted successfully!");
+            if !cli.output.is_machine() && !client.is_dry_run() {
+                println!("Task deleted successfully!");
+            }
         }
-        Commands::Priority { id, priority } => {
-            let priority = priority.parse::<TaskPriority>()?;
+        Commands::Priority { task, priority } => {
+            let id = &task_id(&client, &account, task).await?;
+            let priority = client.parse_priority(priority).await?;
             let task = client.set_task_priority(id, priority).await?;
-            println!("Task priority updated successfully!");
-            print_task(&task);
+            report(&client, cli.output, template.as_ref(), "Task priority updated successfully!", &task)?;
         }
-        Commands::DueDate { id, date } => {
-            let task = client.set_task_due_date(id, date).await?;
-            println!("Task due date updated successfully!");
-            print_task(&task);
+        Commands::DueDate { task, date, until, tz } => {
+            let due_date = DueDate::parse(date, until.as_deref(), tz.as_deref())?;
+            let id = &task_id(&client, &account, task).await?;
+            let task = client.update_task(id, &TaskUpdate::new().due_date(due_date)).await?;
+            report(&client, cli.output, template.as_ref(), "Task due date updated successfully!", &task)?;
         }
-        Commands::Tags { id, tags } => {
-            let task = client.add_task_tags(id, tags).await?;
-            println!("Task tags updated successfully!");
-            print_task(&task);
+        Commands::Tags { command: None, task, tags } => {
+            let id = &task_id(&client, &account, task).await?;
+            let task = client.add_task_tags(id, tags.as_deref().unwrap_or_default()).await?;
+            report(&client, cli.output, template.as_ref(), "Task tags updated successfully!", &task)?;
         }
-        Commands::Description { id, description } => {
+        Commands::Tags { command: Some(command), .. } => match command {
+            TagsCommand::Add { task, tags } => {
+                let id = &task_id(&client, &account, task).await?;
+                let task = client.add_task_tags(id, tags).await?;
+                report(&client, cli.output, template.as_ref(), "Task tags updated successfully!", &task)?;
+            }
+            TagsCommand::Rm { task, tags } => {
+                let id = &task_id(&client, &account, task).await?;
+                let task = client.remove_task_tags(id, tags).await?;
+                report(&client, cli.output, template.as_ref(), "Task tags updated successfully!", &task)?;
+            }
+            TagsCommand::Set { task, tags } => {
+                let id = &task_id(&client, &account, task).await?;
+                let task = client.set_task_tags(id, tags).await?;
+                report(&client, cli.output, template.as_ref(), "Task tags updated successfully!", &task)?;
+            }
+            TagsCommand::List => {
+                let tasks = client.stream_query(TaskQuery::new()).try_collect::<Vec<_>>().await?;
+                let property = &client.property_map().tags.name;
+                let usage = tags::usage(client.schema().await?, property, &tasks);
+                print_tag_usage(cli.output, property, &usage)?;
+            }
+            TagsCommand::Rename { from, to, yes } => {
+                // Renaming the option keeps its color and updates every page
+                // in one request.
+                let question = format!("Rename the tag \"{}\" to \"{}\" on every task?", from, to);
+                if confirmed(&client, cli.output, *yes, "rename a tag", &question)? {
+                    client.rename_option(&client.property_map().tags, from, to).await?;
+                    if !cli.output.is_machine() && !client.is_dry_run() {
+                        println!("{} Renamed the tag {} to {}", "✓".green(), from.bold(), to.bold());
+                    }
+                }
+            }
+            TagsCommand::Merge { from, into, yes } => {
+                let from = from
+                    .iter()
+                    .flat_map(|tags| parse_tags(tags))
+                    .filter(|tag| tag != into)
+                    .collect::<Vec<_>>();
+                let filter = TaskFilter::Or(from.iter().map(|tag| TaskFilter::TagContains(tag.clone())).collect());
+                let action = BulkAction::ReplaceTags { from: from.clone(), to: into.clone() };
+                if bulk(&client, cli.output, &action, TaskQuery::new().filter(filter), |_| true, *yes).await? {
+                    client.remove_options(&client.property_map().tags, &from).await?;
+                    if !cli.output.is_machine() && !client.is_dry_run() {
+                        println!("{} Removed the tag{} {} from the database", "✓".green(), plural(from.len()), from.join(", ").bold());
+                    }
+                }
+            }
+        },
+        Commands::Description { task, description } => {
+            let id = &task_id(&client, &account, task).await?;
             let task = client.set_task_description(id, description).await?;
-            println!("Task description updated successfully!");
-            print_task(&task);
+            report(&client, cli.output, template.as_ref(), "Task description updated successfully!", &task)?;
         }
     }
 
+    if client.is_dry_run() {
+        print_plan(cli.output, &client.take_planned_requests())?;
+    }
     Ok(())
 }
 
-fn print_task(task: &notion_cli_rs::Task) {
-    let status_color = match task.status {
-        TaskStatus::NotStarted => "yellow",
-        TaskStatus::InProgress => "blue",
-        TaskStatus::Done => "green",
+/// Resolves the task argument of a command to the page ID: a `list`
+/// number, an ID prefix, a full ID or a Notion URL, or a title given with
+/// `--match`. When several titles match, the user picks one if stdin and
+/// stdout are terminals.
+async fn task_id(client: &NotionClient, account: &str, task: &TaskArg) -> Result<String> {
+    let text = match (&task.id, &task.title_match) {
+        (Some(input), _) => {
+            let aliases = AliasCache::path(account).and_then(|path| AliasCache::load(&path));
+            return resolve_task_id(client, input, aliases.as_ref()).await;
+        }
+        (None, Some(text)) => text,
+        (None, None) => anyhow::bail!("Give a task ID or --match <TEXT>"),
     };
 
-    println!("ID: {}", task.id.bright_black());
-    println!("Title: {}", task.title.bold());
-    println!("Status: {} {}", 
-        task.status_symbol().color(status_color),
-        task.status.to_string().color(status_color)
-    );
-    println!("Priority: {}", task.priority_symbol());
+    let mut tasks = find_by_title(client, text).await?;
+    if tasks.len() == 1 {
+        return Ok(tasks.remove(0).id);
+    }
+    if !(io::stdin().is_terminal() && io::stdout().is_terminal()) {
+        return Err(ambiguous_title(text, &tasks));
+    }
+
+    println!("{} tasks match \"{}\":", tasks.len(), text);
+    for (i, task) in tasks.iter().enumerate() {
+        println!(
+            "  {:>2}. {} {} {}",
+            i + 1,
+            task.status_symbol(),
+            task.title,
+            template::short_id(&task.id).bright_black()
+        );
+    }
+    let choice = prompt_choice("Task", tasks.len(), None)?;
+    Ok(tasks[choice].id.clone())
+}
+
+/// Remembers the order of `tasks` so that the numbers of the last `list` can
+/// be used as IDs. A cache that cannot be written only costs the numbers.
+fn save_aliases(client: &NotionClient, account: &str, tasks: &[Task]) {
+    let Some(path) = AliasCache::path(account) else { return };
+    if let Err(err) = AliasCache::new(client.database_id(), tasks).save(&path) {
+        log::warn!("{:#}", err);
+    }
+}
+
+/// Asks `question` unless `yes` is set or the run is dry; fails when there
+/// is no terminal to ask on. Returns whether to go ahead.
+fn confirmed(client: &NotionClient, output: OutputFormat, yes: bool, action: &str, question: &str) -> Result<bool> {
+    // A dry run changes nothing, so there is nothing to confirm.
+    if yes || client.is_dry_run() {
+        return Ok(true);
+    }
+    if !io::stdin().is_terminal() || output.is_machine() {
+        anyhow::bail!("Confirmation needed: pass --yes to {} without a prompt", action);
+    }
+    if !prompt(&format!("{} [y/N]", question), None)?.eq_ignore_ascii_case("y") {
+        println!("Nothing changed.");
+        return Ok(false);
+    }
+    Ok(true)
+}
 
-    if let Some(url) = &task.url {
-        println!("    URL: {}", url.bright_blue().underline());
+/// Selects the tasks matching `query` and `keep`, shows them and asks for confirmation
+/// unless `yes` is set, then applies `action` and reports each task.
+/// Returns `false` when the user declined.
+async fn bulk(
+    client: &NotionClient,
+    output: OutputFormat,
+    action: &BulkAction,
+    query: TaskQuery,
+    keep: impl Fn(&Task) -> bool,
+    yes: bool,
+) -> Result<bool> {
+    // A dry run changes nothing, so there is nothing to confirm.
+    let confirm = !yes && !client.is_dry_run();
+    let interactive = io::stdin().is_terminal() && !output.is_machine();
+    if confirm && !interactive {
+        anyhow::bail!("Confirmation needed: pass --yes to {} without a prompt", action);
     }
 
-    if let Some(due) = &task.due_date {
-        println!("    Due: {}", due.bright_yellow());
+    let tasks = client.stream_query(query).try_filter(|task| future::ready(keep(task))).try_collect::<Vec<_>>().await?;
+    if tasks.is_empty() {
+        if !output.is_machine() {
+            println!("No tasks match.");
+        }
+        return Ok(true);
     }
 
-    if !task.tags.is_empty() {
-        println!("    Tags: {}", task.tags.join(", ").blue());
+    if !output.is_machine() {
+        print!("{}", render_tasks(&tasks, &DEFAULT_COLUMNS, terminal_width()));
+        println!();
+    }
+    if confirm {
+        let question = format!("{} {} task{}?", capitalize(&action.to_string()), tasks.len(), plural(tasks.len()));
+        if !prompt(&format!("{} [y/N]", question), None)?.eq_ignore_ascii_case("y") {
+            println!("Nothing changed.");
+            return Ok(false);
+        }
+    }
+
+    let total = tasks.len();
+    let outcomes = bulk::apply(client, tasks, action).await;
+    let mut writer = if output.is_machine() && !client.is_dry_run() { Some(TaskWriter::new(output, io::stdout().lock())?) } else { None };
+    let mut failed = 0;
+    for outcome in &outcomes {
+        match (&outcome.result, writer.as_mut()) {
+            (Ok(Some(task)), Some(writer)) => writer.write(task)?,
+            (Ok(_), Some(_)) => {}
+            (Ok(_), None) if client.is_dry_run() => {}
+            (Ok(_), None) => println!("{} {}", "✓".green(), outcome.task.title),
+            (Err(err), _) => {
+                failed += 1;
+                eprintln!("{} {} {}: {:#}", "✗".red(), outcome.task.title, template::short_id(&outcome.task.id).bright_black(), err);
+            }
+        }
     }
+    if let Some(writer) = writer {
+        writer.finish()?;
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} tasks failed", failed, total);
+    }
+    if !output.is_machine() && !client.is_dry_run() {
+        println!("{} task{} changed.", total, plural(total));
+    }
+    Ok(true)
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Prints the tag options with their task counts, as a colored list or in a
+/// machine-readable format.
+fn print_tag_usage(output: OutputFormat, property: &str, usage: &[TagUsage]) -> Result<()> {
+    match output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), usage)?;
+            println!();
+            return Ok(());
+        }
+        OutputFormat::Ndjson => {
+            for tag in usage {
+                println!("{}", serde_json::to_string(tag)?);
+            }
+            return Ok(());
+        }
+        OutputFormat::Yaml => return Ok(serde_yaml::to_writer(io::stdout().lock(), usage)?),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let delimiter = if output == OutputFormat::Csv { b',' } else { b'\t' };
+            let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(io::stdout().lock());
+            for tag in usage {
+                writer.serialize(tag)?;
+            }
+            writer.flush()?;
+            return Ok(());
+        }
+    }
+
+    if usage.is_empty() {
+        println!("The {} property has no options.", property.bold());
+        return Ok(());
+    }
+    let width = usage.iter().map(|tag| tag.name.chars().count()).max().unwrap_or(0);
+    for tag in usage {
+        let name = format!("{:<width$}", tag.name, width = width);
+        let name = match &tag.color {
+            Some(color) => name.color(notion_color(color)),
+            None => name.normal(),
+        };
+        let count = format!("{:>5}", tag.count);
+        println!("  {}  {}", name, if tag.count == 0 { count.bright_black() } else { count.normal() });
+    }
+    Ok(())
+}
+
+/// Prints the agenda: a table per non-empty bucket, numbered across
+/// buckets. JSON and YAML get every group; the other machine-readable
+/// formats get the tasks in agenda order.
+fn print_agenda(output: OutputFormat, template: Option<&Template>, groups: &[agenda::Group]) -> Result<()> {
+    match output {
+        OutputFormat::Text => {}
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), groups)?;
+            println!();
+            return Ok(());
+        }
+        OutputFormat::Yaml => return Ok(serde_yaml::to_writer(io::stdout().lock(), groups)?),
+        _ => {
+            let mut writer = TaskWriter::new(output, io::stdout().lock())?;
+            for task in groups.iter().flat_map(|group| &group.tasks) {
+                writer.write(task)?;
+            }
+            return writer.finish();
+        }
+    }
+
+    let mut printed = 0;
+    for group in groups.iter().filter(|group| !group.tasks.is_empty()) {
+        if printed > 0 {
+            println!();
+        }
+        let heading = format!("{} ({})", group.bucket, group.tasks.len());
+        match group.bucket {
+            Bucket::Overdue => println!("{}", heading.red().bold()),
+            _ => println!("{}", heading.bold()),
+        }
+        match template {
+            Some(template) => group.tasks.iter().for_each(|task| println!("{}", template.render(task))),
+            None => print!("{}", render_tasks_from(printed, &group.tasks, &DEFAULT_COLUMNS, terminal_width())),
+        }
+        printed += group.tasks.len();
+    }
+    if printed == 0 {
+        println!("No open tasks.");
+    }
+    Ok(())
+}
+
+/// Prints the requests a dry run held back, with the changes each one would
+/// make; machine-readable formats get the requests as data.
+fn print_plan(output: OutputFormat, requests: &[PlannedRequest]) -> Result<()> {
+    match output {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(io::stdout().lock(), requests)?;
+            println!();
+            return Ok(());
+        }
+        OutputFormat::Ndjson => {
+            for request in requests {
+                println!("{}", serde_json::to_string(request)?);
+            }
+            return Ok(());
+        }
+        OutputFormat::Yaml => return Ok(serde_yaml::to_writer(io::stdout().lock(), requests)?),
+        _ => {}
+    }
+
+    for request in requests {
+        println!("{} {} {}", "Would send".yellow().bold(), request.method.bold(), request.url);
+        if let Some(body) = &request.body {
+            for line in serde_json::to_string_pretty(body)?.lines() {
+                println!("    {}", line.bright_black());
+            }
+        }
+        match (&request.before, &request.after) {
+            (None, Some(after)) => println!("  {} {}", "+ create".green(), after.title.bold()),
+            (Some(before), None) => println!("  {} {}", "- delete".red(), before.title.bold()),
+            (Some(before), Some(_)) => {
+                println!("  {} {} {}", "~".yellow(), before.title.bold(), template::short_id(&before.id).bright_black());
+                let changes = request.changes();
+                if changes.is_empty() {
+                    println!("      {}", "no change".bright_black());
+                }
+                for change in changes {
+                    let show = |value: &str| if value.is_empty() { "(none)".to_string() } else { value.to_string() };
+                    println!("      {:<12} {} → {}", change.field, show(&change.before).red(), show(&change.after).green());
+                }
+            }
+            (None, None) => {}
+        }
+        println!();
+    }
+    println!("Dry run: {} request{} not sent.", requests.len(), plural(requests.len()));
+    Ok(())
+}
+
+/// Prints the task a command returned: `message` and the task as text, or
+/// only the task when a template or machine-readable format is used. A dry
+/// run prints its plan instead.
+fn report(client: &NotionClient, output: OutputFormat, template: Option<&Template>, message: &str, task: &Task) -> Result<()> {
+    if client.is_dry_run() {
+        return Ok(());
+    }
+    if output.is_machine() {
+        return write_task(output, task, io::stdout().lock());
+    }
+    if let Some(template) = template {
+        println!("{}", template.render(task));
+        return Ok(());
+    }
+    println!("{}", message);
+    print_task(task);
+    Ok(())
+}
+
+fn print_error(err: &anyhow::Error) {
+    match err.downcast_ref::<NotionError>() {
+        Some(notion_err) => eprintln!(
+            "{} [{}] {}",
+            "Error:".red().bold(),
+            notion_err.code().yellow(),
+            notion_err.message()
+        ),
+        None => eprintln!("{} {:#}", "Error:".red().bold(), err),
+    }
+}
+
+/// Reads one line from stdin after printing `question`; an empty answer
+/// returns `default`.
+fn prompt(question: &str, default: Option<&str>) -> Result<String> {
+    match default {
+        Some(default) if !default.is_empty() => print!("{} [{}]: ", question, default),
+        _ => print!("{}: ", question),
+    }
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        anyhow::bail!("No answer given to \"{}\"", question);
+    }
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default.unwrap_or_default().to_string() } else { answer.to_string() })
+}
+
+/// Asks for a number between 1 and `count`.
+fn prompt_choice(question: &str, count: usize, default: Option<usize>) -> Result<usize> {
+    let default = default.map(|d| (d + 1).to_string());
+    loop {
+        let answer = prompt(question, default.as_deref())?;
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => return Ok(n - 1),
+            _ => println!("Please enter a number between 1 and {}.", count),
+        }
+    }
+}
+
+/// Default options of the properties `init` creates.
+fn default_options(field: &str) -> &'static [&'static str] {
+    match field {
+        "status" => &["Not started", "In progress", "Done"],
+        "priority" => &["High", "Medium", "Low"],
+        _ => &[],
+    }
+}
+
+async fn init(profile: Option<&str>, dry_run: bool) -> Result<()> {
+    let path = ConfigFile::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine the config directory; set NOTION_CONFIG"))?;
+    let mut file = ConfigFile::load(&path)?;
+
+    let suggested_name = profile.or(file.default_profile.as_deref()).unwrap_or("default").to_string();
+    let name = prompt("Profile name", Some(&suggested_name))?;
+    let existing = file.profiles.get(&name).cloned().unwrap_or_default();
+
+    let current_token = existing.token.clone().or_else(|| secrets::keyring_token(&name).ok().flatten());
+    let (token, client) = prompt_token(current_token.as_deref()).await?;
+
+    let databases = client.search_databases().await?;
+    if databases.is_empty() {
+        anyhow::bail!(
+            "The integration cannot see any database. Open your task database in Notion, \
+             choose ••• → Connections, add the integration and run init again"
+        );
+    }
+
+    println!();
+    println!("{}", "Databases shared with the integration".bold());
+    for (i, database) in databases.iter().enumerate() {
+        let title = if database.title.is_empty() { "Untitled" } else { database.title.as_str() };
+        println!("  {:>2}. {} {}", i + 1, title, database.id.bright_black());
+    }
+    let current = databases.iter().position(|d| existing.database_id.as_deref().is_some_and(|id| same_id(id, &d.id)));
+    let mut schema = databases[prompt_choice("Database", databases.len(), current.or(Some(0)))?].clone();
+
+    let database_client = init_client(&token, &schema.id, dry_run)?;
+    let properties = map_properties(&database_client, &mut schema, &existing.properties).await?;
+
+    if dry_run {
+        println!();
+        print_plan(OutputFormat::Text, &database_client.take_planned_requests())?;
+        println!(
+            "{} Profile {} would be saved to {} and its token stored; nothing was changed.",
+            "Dry run:".yellow().bold(),
+            name.bold(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let stored = store_token(&name, &token, false)?;
+    file.profiles.insert(name.clone(), Profile {
+        token: None,
+        database_id: Some(schema.id.clone()),
+        properties,
+        defaults: existing.defaults,
+    });
+    if file.default_profile.is_none() {
+        file.default_profile = Some(name.clone());
+    }
+    file.save(&path)?;
+
+    println!();
+    println!("{} Profile {} saved to {}, token stored in the {}", "✓".green(), name.bold(), path.display(), stored);
+    if file.default_profile.as_deref() != Some(name.as_str()) {
+        println!("Use it with --profile {} or NOTION_PROFILE={}", name, name);
+    }
+    Ok(())
+}
+
+/// The client `init` creates missing properties with; in a dry run it only
+/// plans the database changes.
+fn init_client(token: &str, database_id: &str, dry_run: bool) -> Result<NotionClient> {
+    let client = NotionClient::new(Config {
+        notion_token: token.to_string(),
+        database_id: database_id.to_string(),
+        ..Config::default()
+    })?;
+    Ok(if dry_run { client.with_dry_run() } else { client })
+}
+
+/// Asks for an integration token until Notion accepts one. An empty answer
+/// keeps `current` when there is one.
+async fn prompt_token(current: Option<&str>) -> Result<(String, NotionClient)> {
+    println!();
+    println!("Create an integration at {} and copy its token.", "https://www.notion.so/my-integrations".underline());
+    loop {
+        let question = if current.is_some() { "Integration token (leave empty to keep the current one): " } else { "Integration token: " };
+        let token = rpassword::prompt_password(question)?;
+        let token = match (token.trim(), current) {
+            ("", Some(current)) => current.to_string(),
+            ("", None) => continue,
+            (token, _) => token.to_string(),
+        };
+
+        let client = NotionClient::new(Config { notion_token: token.clone(), ..Config::default() })?;
+        match client.me().await {
+            Ok(bot) => {
+                println!(
+                    "{} Connected as {} in workspace {}",
+                    "✓".green(),
+                    bot.name.as_deref().unwrap_or(&bot.id).bold(),
+                    bot.workspace_name.as_deref().unwrap_or("unknown").bold()
+                );
+                return Ok((token, client));
+            }
+            Err(err) if matches!(err.downcast_ref::<NotionError>(), Some(NotionError::Unauthorized { .. })) => {
+                println!("{} The token was rejected by Notion, please try again.", "✗".red());
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Stores `token` for `account` in the system keyring, or in the encrypted
+/// token file when `to_file` is set or the keyring is unavailable.
+fn store_token(account: &str, token: &str, to_file: bool) -> Result<TokenSource> {
+    if !to_file {
+        match secrets::store_in_keyring(account, token) {
+            Ok(()) => {
+                secrets::delete_token_file(account)?;
+                return Ok(TokenSource::Keyring);
+            }
+            Err(err) => println!("{} {:#}; using an encrypted file instead.", "Warning:".yellow().bold(), err),
+        }
+    }
+
+    println!("Choose a passphrase to encrypt the token (set NOTION_TOKEN_PASSPHRASE to skip this prompt later).");
+    let path = secrets::store_in_file(account, token, &secrets::passphrase(true)?)?;
+    log::debug!("Token written to {}", path.display());
+    Ok(TokenSource::EncryptedFile)
+}
+
+/// The profile `auth` commands work on, with the config file and its path.
+fn auth_profile(requested: Option<&str>) -> Result<(String, ConfigFile, std::path::PathBuf)> {
+    let path = ConfigFile::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine the config directory; set NOTION_CONFIG"))?;
+    let file = ConfigFile::load(&path)?;
+    let requested = requested.map(str::to_string).or_else(|| std::env::var("NOTION_PROFILE").ok());
+
+    let name = match requested {
+        Some(name) => name,
+        None => file.select(None)?.map_or("default", |(name, _)| name).to_string(),
+    };
+    Ok((name, file, path))
+}
+
+async fn auth(command: &AuthCommand, profile: Option<&str>, dry_run: bool) -> Result<()> {
+    let (name, mut file, path) = auth_profile(profile)?;
+
+    match command {
+        AuthCommand::Login { file: to_file } => {
+            let (token, _) = prompt_token(None).await?;
+            if dry_run {
+                let target = if *to_file { TokenSource::EncryptedFile } else { TokenSource::Keyring };
+                println!(
+                    "{} The token for profile {} would be stored in the {}; nothing was changed.",
+                    "Dry run:".yellow().bold(),
+                    name.bold(),
+                    target
+                );
+                return Ok(());
+            }
+            let stored = store_token(&name, &token, *to_file)?;
+
+            // A new profile is added so that --profile finds it; a plaintext
+            // token left in the file would take precedence, so drop it.
+            let had_profile = file.profiles.contains_key(&name);
+            let entry = file.profiles.entry(name.clone()).or_default();
+            if entry.token.take().is_some() || (!had_profile && profile.is_some()) {
+                file.save(&path)?;
+            }
+
+            println!("{} Token for profile {} stored in the {}", "✓".green(), name.bold(), stored);
+            if std::env::var_os("NOTION_TOKEN").is_some() {
+                println!("{} NOTION_TOKEN is set and takes precedence over the stored token.", "Warning:".yellow().bold());
+            }
+        }
+        AuthCommand::Logout if dry_run => {
+            let mut found = Vec::new();
+            if secrets::keyring_token(&name).ok().flatten().is_some() {
+                found.push(TokenSource::Keyring.to_string());
+            }
+            if secrets::token_file_path(&name).is_some_and(|path| path.exists()) {
+                found.push(TokenSource::EncryptedFile.to_string());
+            }
+            if file.profiles.get(&name).is_some_and(|p| p.token.is_some()) {
+                found.push(TokenSource::ConfigFile.to_string());
+            }
+
+            if found.is_empty() {
+                println!("No stored token for profile {}.", name.bold());
+            } else {
+                println!(
+                    "{} The token of profile {} would be removed from the {}; nothing was changed.",
+                    "Dry run:".yellow().bold(),
+                    name.bold(),
+                    found.join(" and ")
+                );
+            }
+        }
+        AuthCommand::Logout => {
+            let mut removed = Vec::new();
+            if secrets::delete_from_keyring(&name).unwrap_or(false) {
+                removed.push(TokenSource::Keyring.to_string());
+            }
+            if secrets::delete_token_file(&name)? {
+                removed.push(TokenSource::EncryptedFile.to_string());
+            }
+            if file.profiles.get_mut(&name).and_then(|p| p.token.take()).is_some() {
+                file.save(&path)?;
+                removed.push(TokenSource::ConfigFile.to_string());
+            }
+
+            if removed.is_empty() {
+                println!("No stored token for profile {}.", name.bold());
+            } else {
+                println!("{} Removed the token of profile {} from the {}", "✓".green(), name.bold(), removed.join(" and "));
+            }
+        }
+        AuthCommand::Status => {
+            let mut config = Config::load_unchecked(profile)?;
+            if config.token_source.is_none() {
+                if let Some((token, source)) = secrets::stored_token(config.account())? {
+                    config.notion_token = token;
+                    config.token_source = Some(source);
+                }
+            }
+
+            println!("Profile: {}", config.profile.as_deref().unwrap_or("(none)").bold());
+            let Some(source) = config.token_source else {
+                println!("Token: {}", "not configured".red());
+                println!("Run `notion-cli-rs auth login` to store one.");
+                return Ok(());
+            };
+            println!("Token: {} from the {}", secrets::mask_token(&config.notion_token), source);
+            if source.is_plaintext() {
+                println!("{} The token is stored in plaintext; run `notion-cli-rs auth login` to move it to the keyring.", "Warning:".yellow().bold());
+            }
+
+            let client = NotionClient::new(config)?;
+            let bot = client.me().await?;
+            println!(
+                "{} Valid, connected as {} in workspace {}",
+                "✓".green(),
+                bot.name.as_deref().unwrap_or(&bot.id).bold(),
+                bot.workspace_name.as_deref().unwrap_or("unknown").bold()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Maps every task field to a property of `schema`, asking the user to pick
+/// or create a property for each field that has no obvious match.
+async fn map_properties(client: &NotionClient, schema: &mut DatabaseSchema, current: &PropertyMap) -> Result<PropertyMap> {
+    let mut map = schema.suggest_mapping(current);
+    let missing = map.fields()
+        .iter()
+        .filter(|(_, mapped)| !schema.fits(mapped))
+        .map(|(field, mapped)| (*field, (*mapped).clone()))
+        .collect::<Vec<_>>();
+
+    for (field, mapped) in missing {
+        println!();
+        println!("{} The task field {} has no matching property.", "!".yellow().bold(), field.bold());
+
+        let candidates = schema.candidates(field);
+        for (i, property) in candidates.iter().enumerate() {
+            println!("  {:>2}. Use {} ({})", i + 1, property.name, property.kind);
+        }
+        let create = candidates.len() + 1;
+        let create_kind = match mapped.kind {
+            PropertyKind::Status => PropertyKind::Select,
+            kind => kind,
+        };
+        println!("  {:>2}. Create a {} property named \"{}\"", create, create_kind, mapped.name);
+        println!("  {:>2}. Skip", create + 1);
+
+        let choice = prompt_choice("Choice", create + 1, Some(0))? + 1;
+        if choice <= candidates.len() {
+            let property = candidates[choice - 1];
+            map.set(field, PropertyRef::new(property.name.clone(), property.kind.parse()?))?;
+        } else if choice == create {
+            let property = PropertyRef::new(mapped.name.clone(), create_kind);
+            *schema = client.create_property(&property, default_options(field)).await?;
+            map.set(field, property)?;
+            let done = if client.is_dry_run() { "Would create" } else { "Created" };
+            println!("{} {} {}", "✓".green(), done, mapped.name);
+        }
+    }
+
+    for problem in schema.mapping_problems(&map) {
+        println!("{} {}", "Warning:".yellow().bold(), problem);
+    }
+    Ok(map)
+}
+
+/// Compares Notion IDs with or without dashes.
+fn same_id(a: &str, b: &str) -> bool {
+    a.replace('-', "").eq_ignore_ascii_case(&b.replace('-', ""))
+}
+
+fn print_profiles() -> Result<()> {
+    let path = ConfigFile::default_path()
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine the config directory; set NOTION_CONFIG"))?;
+    let file = ConfigFile::load(&path)?;
+
+    println!("Config file: {}", path.display().to_string().bright_black());
+    if file.profiles.is_empty() {
+        println!("No profiles configured.");
+        return Ok(());
+    }
+
+    let env_profile = std::env::var("NOTION_PROFILE").ok();
+    let active = file.select(env_profile.as_deref()).ok().flatten().map(|(name, _)| name);
+    for (name, profile) in &file.profiles {
+        let mark = if Some(name.as_str()) == active { "*".green() } else { " ".normal() };
+        println!("{} {:<16} {}", mark, name.bold(), profile.database_id.as_deref().unwrap_or("-").bright_black());
+    }
+    Ok(())
+}
+
+fn print_schema(schema: &notion_cli_rs::DatabaseSchema, map: &notion_cli_rs::PropertyMap) {
+    println!("Database: {} {}", schema.title.bold(), schema.id.bright_black());
+
+    println!();
+    println!("{}", "Properties".bold());
+    let width = schema.properties.iter().map(|p| p.name.chars().count()).max().unwrap_or(0);
+    for property in &schema.properties {
+        let options = property.options
+            .iter()
+            .map(|o| match (&o.color, o.group) {
+                (Some(color), Some(group)) => format!("{} [{}]", o.name.color(notion_color(color)), group),
+                (Some(color), None) => o.name.color(notion_color(color)).to_string(),
+                (None, _) => o.name.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("    {:<width$}  {:<14} {}", property.name, property.kind, options, width = width);
+    }
+
+    println!();
+    println!("{}", "Task field mapping".bold());
+    let problems = schema.mapping_problems(map);
+    for (field, mapped) in map.fields() {
+        let mark = if schema.fits(mapped) { "✓".green() } else { "✗".red() };
+        println!("    {} {:<12} → {} ({})", mark, field, mapped.name, mapped.kind);
+    }
+
+    if !problems.is_empty() {
+        println!();
+        for problem in problems {
+            println!("{} {}", "Warning:".yellow().bold(), problem);
+        }
+        println!("Override the mapping with NOTION_PROPERTY_<FIELD>=<name>[:<type>], e.g. NOTION_PROPERTY_DUE_DATE=Deadline,");
+        println!("or in the [profiles.<name>.properties] table of the config file");
+    }
+}
+
+fn print_task_details(details: &notion_cli_rs::TaskDetails) {
+    print_task(&details.task);
+
+    println!();
+    println!("{}", "Properties".bold());
+    let width = details.properties.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    for (name, value) in &details.properties {
+        let value = if value.is_empty() { "-".bright_black().to_string() } else { value.clone() };
+        println!("    {:<width$}  {}", name, value, width = width);
+    }
+
+    println!();
+    if let Some(created) = &details.task.created_time {
+        println!("Created: {} by {}", created, details.created_by.as_deref().unwrap_or("unknown"));
+    }
+    if let Some(edited) = &details.task.last_edited_time {
+        println!("Last edited: {} by {}", edited, details.last_edited_by.as_deref().unwrap_or("unknown"));
+    }
+
+    if !details.content.is_empty() {
+        println!();
+        println!("{}", "Content".bold());
+        for line in &details.content {
+            for part in line.lines() {
+                println!("    {}", part);
+            }
+        }
+    }
+}
+
+fn print_task(task: &notion_cli_rs::Task) {
+    let template = Template::parse(DEFAULT_TEMPLATE).expect("the default template is valid");
+    println!("{}", template.render(task));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_init_honors_dry_run() {
+        let cli = Cli::try_parse_from(["notion-cli-rs", "--dry-run", "init"]).unwrap();
+        assert!(cli.dry_run);
+        assert!(init_client("secret_abc", "database-id", cli.dry_run).unwrap().is_dry_run());
+        assert!(!init_client("secret_abc", "database-id", false).unwrap().is_dry_run());
+    }
+
+    #[test]
+    fn test_list_columns() {
+        let cli = Cli::try_parse_from(["notion-cli-rs", "list", "--columns", "title,due"]).unwrap();
+        let Commands::List { columns, .. } = cli.command else { panic!("expected list") };
+        assert_eq!(columns, Some(vec![Column::Title, Column::Due]));
+
+        assert!(Cli::try_parse_from(["notion-cli-rs", "list", "--columns", "title,owner"]).is_err());
+    }
+
+    #[test]
+    fn test_list_sort_keys() {
+        let cli = Cli::try_parse_from(["notion-cli-rs", "list", "--sort", "priority,-due"]).unwrap();
+        let Commands::List { sort, .. } = cli.command else { panic!("expected list") };
+        assert_eq!(
+            sort,
+            Some(vec![
+                TaskSort { property: SortProperty::Priority, direction: SortDirection::Ascending },
+                TaskSort { property: SortProperty::DueDate, direction: SortDirection::Descending },
+            ])
+        );
+
+        let cli = Cli::try_parse_from(["notion-cli-rs", "list", "--sort", "-due"]).unwrap();
+        let Commands::List { sort, .. } = cli.command else { panic!("expected list") };
+        assert_eq!(sort, Some(vec![TaskSort { property: SortProperty::DueDate, direction: SortDirection::Descending }]));
+
+        assert!(Cli::try_parse_from(["notion-cli-rs", "list", "--sort", "size"]).is_err());
+    }
+
+    fn list_filters(flag: &str) -> FilterArgs {
+        let cli = Cli::try_parse_from(["notion-cli-rs", "list", flag]).unwrap();
+        let Commands::List { filters, .. } = cli.command else { panic!("expected list") };
+        filters
+    }
+
+    fn task(status: &str, due: &str) -> Task {
+        serde_json::from_value(serde_json::json!({ "id": "abc", "title": "Task", "status": status, "due_date": due })).unwrap()
+    }
+
+    #[test]
+    fn test_due_flags_use_the_end_of_date_ranges() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let ongoing = task("Not started", "2024-01-15/2099-01-01");
+        let ended = task("Not started", "2024-01-15/2024-01-20");
+        let due_today = task("Not started", "2026-10-18");
+
+        let overdue = list_filters("--overdue");
+        assert!(!overdue.keeps(&ongoing, today));
+        assert!(overdue.keeps(&ended, today));
+        assert!(!overdue.keeps(&due_today, today));
+        assert!(!overdue.keeps(&task("Done", "2024-01-15/2024-01-20"), today));
 
-    if let Some(desc) = &task.description {
-        println!("    Description: {}", desc);
+        let today_only = list_filters("--due-today");
+        assert!(today_only.keeps(&ongoing, today));
+        assert!(!today_only.keeps(&ended, today));
+        assert!(today_only.keeps(&due_today, today));
+        assert!(!today_only.keeps(&task("Not started", "2026-10-19/2026-10-25"), today));
     }
 }
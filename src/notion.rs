@@ -1,23 +1,100 @@
-use crate::config::Config;
+use crate::config::{Config, PropertyKind, PropertyMap, PropertyRef};
+use crate::dates::DueDate;
+use crate::dry_run::PlannedRequest;
+use crate::error::NotionError;
+use crate::retry::{RateLimiter, RetryPolicy};
 use anyhow::Result;
-use reqwest::Client;
+use chrono::{Local, NaiveDate};
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::{Client, Method};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json;
 use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
 
-#[derive(Debug, Clone, PartialEq)]
+/// A task read from a page of the database.
+///
+/// Serializes to the stable schema documented in the README's "Output
+/// formats" section: status and priority are option names, absent values
+/// are `null`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub title: String,
     pub status: TaskStatus,
+    /// Color of the status option in Notion, e.g. `blue`.
+    pub status_color: Option<String>,
+    /// Group of the status option, known once the client has loaded the
+    /// database schema. Not part of the output formats.
+    #[serde(skip)]
+    pub status_group: Option<StatusGroup>,
     pub url: Option<String>,
     pub priority: Option<TaskPriority>,
-    pub due_date: Option<String>,
+    /// Color of the priority option in Notion.
+    pub priority_color: Option<String>,
+    pub due_date: Option<DueDate>,
+    #[serde(default)]
     pub tags: Vec<String>,
     pub description: Option<String>,
+    pub created_time: Option<String>,
+    pub last_edited_time: Option<String>,
+    /// ID of the user who created the page.
+    pub created_by: Option<String>,
+    /// ID of the user who last edited the page.
+    pub last_edited_by: Option<String>,
 }
 
 #[allow(dead_code)]
 impl Task {
+    /// Builds a task from a Notion page object, as returned by page
+    /// creation, page updates and database queries, reading each field from
+    /// the property `map` points it to.
+    pub fn from_page(page: &serde_json::Value, map: &PropertyMap) -> Result<Self> {
+        let properties = &page["properties"];
+        let value = |property: &PropertyRef| &properties[property.name.as_str()][property.kind.as_str()];
+
+        let status = TaskStatus::from_name(value(&map.status)["name"].as_str().unwrap_or("Not started"));
+        let status_color = value(&map.status)["color"].as_str().map(|s| s.to_string());
+
+        let priority = value(&map.priority)["name"].as_str().map(TaskPriority::from_name);
+        let priority_color = value(&map.priority)["color"].as_str().map(|s| s.to_string());
+
+        let due_date = DueDate::from_notion(value(&map.due_date));
+
+        let tags = value(&map.tags)
+            .as_array()
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|tag| tag["name"].as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let description = Some(rich_text_to_string(value(&map.description)))
+            .filter(|text| !text.is_empty());
+
+        Ok(Task {
+            id: page["id"].as_str().unwrap_or_default().to_string(),
+            title: rich_text_to_string(value(&map.title)),
+            status,
+            status_color,
+            status_group: None,
+            url: page["url"].as_str().map(|s| s.to_string()),
+            priority,
+            priority_color,
+            due_date,
+            tags,
+            description,
+            created_time: page["created_time"].as_str().map(|s| s.to_string()),
+            last_edited_time: page["last_edited_time"].as_str().map(|s| s.to_string()),
+            created_by: page["created_by"]["id"].as_str().map(|s| s.to_string()),
+            last_edited_by: page["last_edited_by"]["id"].as_str().map(|s| s.to_string()),
+        })
+    }
+
     pub fn status_symbol(&self) -> &str {
         self.status.symbol()
     }
@@ -28,13 +105,202 @@ impl Task {
             None => " ",
         }
     }
+
+    /// Whether the status is in the Complete group, or is `Done` when the
+    /// group is unknown.
+    pub fn is_finished(&self) -> bool {
+        match self.status_group {
+            Some(group) => group == StatusGroup::Complete,
+            None => self.status == TaskStatus::Done,
+        }
+    }
+
+    /// Whether the task is not finished and its due date, or the end of
+    /// its date range, was before `today` in the local time zone.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        !self.is_finished() && self.due_date.is_some_and(|due| due.end_date_in(&Local) < today)
+    }
+
+    /// Whether `day` falls on the due date, or within its date range, in
+    /// the local time zone.
+    pub fn is_due_on(&self, day: NaiveDate) -> bool {
+        self.due_date.is_some_and(|due| due.date_in(&Local) <= day && day <= due.end_date_in(&Local))
+    }
+}
+
+#[cfg(test)]
+impl Task {
+    /// A `Not started` task with only a title, for tests to fill in with
+    /// the fields they need.
+    pub(crate) fn for_test(title: &str) -> Self {
+        Task {
+            id: "abc".to_string(),
+            title: title.to_string(),
+            status: TaskStatus::NotStarted,
+            status_color: None,
+            status_group: None,
+            url: None,
+            priority: None,
+            priority_color: None,
+            due_date: None,
+            tags: Vec::new(),
+            description: None,
+            created_time: None,
+            last_edited_time: None,
+            created_by: None,
+            last_edited_by: None,
+        }
+    }
+}
+
+/// Joins the segments of a rich text array into plain text, preferring
+/// Notion's `plain_text` and falling back to the raw text content.
+fn rich_text_to_string(rich_text: &serde_json::Value) -> String {
+    rich_text
+        .as_array()
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(|segment| {
+                    segment["plain_text"]
+                        .as_str()
+                        .or_else(|| segment["text"]["content"].as_str())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Renders any Notion property value as plain text for display.
+pub fn property_to_string(property: &serde_json::Value) -> String {
+    let kind = property["type"].as_str().unwrap_or_default();
+    let value = &property[kind];
+    match kind {
+        "title" | "rich_text" => rich_text_to_string(value),
+        "select" | "status" => value["name"].as_str().unwrap_or_default().to_string(),
+        "multi_select" => value
+            .as_array()
+            .map(|options| {
+                options.iter()
+                    .filter_map(|option| option["name"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        "date" => date_to_string(value),
+        "people" => value
+            .as_array()
+            .map(|people| {
+                people.iter()
+                    .map(|person| person["name"].as_str().or(person["id"].as_str()).unwrap_or_default())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        "files" => value
+            .as_array()
+            .map(|files| {
+                files.iter()
+                    .filter_map(|file| file["name"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        "relation" => value
+            .as_array()
+            .map(|pages| {
+                pages.iter()
+                    .filter_map(|page| page["id"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default(),
+        "checkbox" => if value.as_bool().unwrap_or(false) { "Yes" } else { "No" }.to_string(),
+        "created_by" | "last_edited_by" => value["name"]
+            .as_str()
+            .or(value["id"].as_str())
+            .unwrap_or_default()
+            .to_string(),
+        "formula" | "rollup" => {
+            let inner_kind = value["type"].as_str().unwrap_or_default();
+            match inner_kind {
+                "date" => date_to_string(&value["date"]),
+                "array" => format!("{} items", value["array"].as_array().map_or(0, |a| a.len())),
+                _ => scalar_to_string(&value[inner_kind]),
+            }
+        }
+        "unique_id" => match value["prefix"].as_str() {
+            Some(prefix) => format!("{}-{}", prefix, scalar_to_string(&value["number"])),
+            None => scalar_to_string(&value["number"]),
+        },
+        _ => scalar_to_string(value),
+    }
+}
+
+fn date_to_string(date: &serde_json::Value) -> String {
+    match (date["start"].as_str(), date["end"].as_str()) {
+        (Some(start), Some(end)) => format!("{} → {}", start, end),
+        (Some(start), None) => start.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn scalar_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Renders a block from the page body as one line of text, or `None` for
+/// blocks without text content.
+fn block_to_string(block: &serde_json::Value) -> Option<String> {
+    let kind = block["type"].as_str()?;
+    let value = &block[kind];
+    let text = rich_text_to_string(&value["rich_text"]);
+    let line = match kind {
+        "heading_1" => format!("# {}", text),
+        "heading_2" => format!("## {}", text),
+        "heading_3" => format!("### {}", text),
+        "bulleted_list_item" => format!("• {}", text),
+        "numbered_list_item" => format!("- {}", text),
+        "to_do" => {
+            let mark = if value["checked"].as_bool().unwrap_or(false) { "x" } else { " " };
+            format!("[{}] {}", mark, text)
+        }
+        "quote" | "callout" => format!("> {}", text),
+        "code" => format!("```\n{}\n```", text),
+        "divider" => "---".to_string(),
+        "child_page" | "child_database" => format!("[{}]", value["title"].as_str().unwrap_or_default()),
+        "paragraph" | "toggle" => text,
+        _ => return None,
+    };
+    Some(line)
+}
+
+/// A task together with everything `show` displays about its page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskDetails {
+    pub task: Task,
+    /// Every property of the page, rendered as text, in name order.
+    pub properties: Vec<(String, String)>,
+    /// Name of the user who created the page, or their ID if it cannot be read.
+    pub created_by: Option<String>,
+    /// Name of the user who last edited the page, or their ID if it cannot be read.
+    pub last_edited_by: Option<String>,
+    /// The page body, one line per block.
+    pub content: Vec<String>,
 }
 
+/// A task status. The three default Notion statuses have their own
+/// variants; any other option of the database is kept by name in `Other`.
 #[derive(Debug, Clone, PartialEq)]
 pub enum TaskStatus {
     NotStarted,
     InProgress,
     Done,
+    Other(String),
 }
 
 impl fmt::Display for TaskStatus {
@@ -43,6 +309,7 @@ impl fmt::Display for TaskStatus {
             TaskStatus::NotStarted => write!(f, "Not started"),
             TaskStatus::InProgress => write!(f, "In progress"),
             TaskStatus::Done => write!(f, "Done"),
+            TaskStatus::Other(name) => write!(f, "{}", name),
         }
     }
 }
@@ -53,8 +320,15 @@ impl TaskStatus {
             TaskStatus::NotStarted => "⭕",
             TaskStatus::InProgress => "🔄",
             TaskStatus::Done => "✅",
+            TaskStatus::Other(_) => "🔹",
         }
     }
+
+    /// Maps an option name to a status, accepting any name. Use
+    /// [`DatabaseSchema::resolve_status`] to validate user input.
+    pub fn from_name(name: &str) -> Self {
+        name.parse().unwrap_or_else(|_| TaskStatus::Other(name.to_string()))
+    }
 }
 
 impl std::str::FromStr for TaskStatus {
@@ -70,11 +344,29 @@ impl std::str::FromStr for TaskStatus {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl Serialize for TaskStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(TaskStatus::from_name(&String::deserialize(deserializer)?))
+    }
+}
+
+/// A task priority. `High`, `Medium` and `Low` have their own variants;
+/// any other option of the database is kept by name in `Other`.
+///
+/// Priorities order by urgency: `High > Medium > Low`, and other options
+/// rank below `Low`, by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TaskPriority {
     High,
     Medium,
     Low,
+    Other(String),
 }
 
 impl fmt::Display for TaskPriority {
@@ -83,6 +375,7 @@ impl fmt::Display for TaskPriority {
             TaskPriority::High => write!(f, "High"),
             TaskPriority::Medium => write!(f, "Medium"),
             TaskPriority::Low => write!(f, "Low"),
+            TaskPriority::Other(name) => write!(f, "{}", name),
         }
     }
 }
@@ -93,10 +386,41 @@ impl TaskPriority {
             TaskPriority::High => "🔴",
             TaskPriority::Medium => "🟡",
             TaskPriority::Low => "🟢",
+            TaskPriority::Other(_) => "🔸",
+        }
+    }
+
+    /// Maps an option name to a priority, accepting any name. Use
+    /// [`DatabaseSchema::resolve_priority`] to validate user input.
+    pub fn from_name(name: &str) -> Self {
+        name.parse().unwrap_or_else(|_| TaskPriority::Other(name.to_string()))
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            TaskPriority::High => 3,
+            TaskPriority::Medium => 2,
+            TaskPriority::Low => 1,
+            TaskPriority::Other(_) => 0,
         }
     }
 }
 
+impl Ord for TaskPriority {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank()).then_with(|| match (self, other) {
+            (TaskPriority::Other(a), TaskPriority::Other(b)) => a.cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        })
+    }
+}
+
+impl PartialOrd for TaskPriority {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::str::FromStr for TaskPriority {
     type Err = anyhow::Error;
 
@@ -110,377 +434,1497 @@ impl std::str::FromStr for TaskPriority {
     }
 }
 
-#[allow(dead_code)]
-pub struct NotionClient {
-    client: Client,
-    config: Config,
-    api_url: String,
-}
-
-#[allow(dead_code)]
-impl NotionClient {
-    pub fn new(config: Config) -> Result<Self> {
-        Ok(Self {
-            config,
-            client: reqwest::Client::new(),
-            api_url: "https://api.notion.com".to_string(),
-        })
+impl Serialize for TaskPriority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
     }
+}
 
-    pub fn new_with_base_url(config: Config, base_url: String) -> Result<Self> {
-        Ok(Self {
-            config,
-            client: reqwest::Client::new(),
-            api_url: base_url,
-        })
+impl<'de> Deserialize<'de> for TaskPriority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Ok(TaskPriority::from_name(&String::deserialize(deserializer)?))
     }
+}
 
-    pub async fn add_task(&self, title: &str) -> Result<Task> {
-        let url = format!("{}/v1/pages", self.api_url);
-        let body = serde_json::json!({
-            "parent": { "database_id": self.config.database_id },
-            "properties": {
-                "Name": {
-                    "title": [
-                        {
-                            "type": "text",
-                            "text": {
-                                "content": title,
-                                "link": null
-                            }
-                        }
-                    ]
-                },
-                "Status": {
-                    "status": {
-                        "name": "Not started"
-                    }
-                }
-            }
-        });
+/// Builds a `title` property value from plain text.
+fn title_value(text: &str) -> serde_json::Value {
+    serde_json::json!({ "title": [text_object(text)] })
+}
 
-        let response = self.client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+/// Builds a `rich_text` property value from plain text.
+fn rich_text_value(text: &str) -> serde_json::Value {
+    serde_json::json!({ "rich_text": [text_object(text)] })
+}
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status: TaskStatus::NotStarted,
-            url: response["url"].as_str().map(|s| s.to_string()),
-            priority: None,
-            due_date: None,
-            tags: Vec::new(),
-            description: None,
-        };
+fn text_object(text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "type": "text",
+        "text": {
+            "content": text,
+            "link": null
+        }
+    })
+}
 
-        Ok(task)
-    }
+/// Wraps `value` in an object keyed by the property type, the shape Notion
+/// uses for property values and filter conditions alike.
+fn typed_value(kind: PropertyKind, value: serde_json::Value) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+    object.insert(kind.as_str().to_string(), value);
+    serde_json::Value::Object(object)
+}
 
-    pub async fn list_tasks(&self) -> Result<Vec<Task>> {
-        let url = format!("{}/v1/databases/{}/query", self.api_url, self.config.database_id);
-        let response = self.client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({}))
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
-
-        let mut tasks = Vec::new();
-        if let Some(results) = response["results"].as_array() {
-            for result in results {
-                let status = result["properties"]["Status"]["status"]["name"]
-                    .as_str()
-                    .unwrap_or("Not started")
-                    .parse::<TaskStatus>()?;
-
-                let priority = result["properties"]["Priority"]["select"]["name"]
-                    .as_str()
-                    .map(|p| p.parse::<TaskPriority>().ok())
-                    .flatten();
-
-                let due_date = result["properties"]["Due Date"]["date"]["start"]
-                    .as_str()
-                    .map(|s| s.to_string());
-
-                let tags = result["properties"]["Tags"]["multi_select"]
-                    .as_array()
-                    .map(|tags| {
-                        tags.iter()
-                            .filter_map(|tag| tag["name"].as_str())
-                            .map(|s| s.to_string())
-                            .collect()
-                    })
-                    .unwrap_or_default();
+/// Builds a `select` or `status` property value choosing the option `name`.
+fn option_value(property: &PropertyRef, name: &str) -> serde_json::Value {
+    typed_value(property.kind, serde_json::json!({ "name": name }))
+}
 
-                let description = result["properties"]["Description"]["rich_text"]
-                    .as_array()
-                    .and_then(|texts| texts.first())
-                    .and_then(|text| text["text"]["content"].as_str())
-                    .map(|s| s.to_string());
+/// Builds a filter object for one property.
+fn property_filter(property: &PropertyRef, condition: serde_json::Value) -> serde_json::Value {
+    let mut filter = typed_value(property.kind, condition);
+    filter["property"] = serde_json::json!(property.name);
+    filter
+}
 
-                let task = Task {
-                    id: result["id"].as_str().unwrap_or_default().to_string(),
-                    title: result["properties"]["Name"]["title"][0]["text"]["content"]
-                        .as_str()
-                        .unwrap_or_default()
-                        .to_string(),
-                    status,
-                    url: result["url"].as_str().map(|s| s.to_string()),
-                    priority,
-                    due_date,
-                    tags,
-                    description,
-                };
-                tasks.push(task);
-            }
-        }
+fn date_value(due_date: &DueDate) -> serde_json::Value {
+    serde_json::json!({ "date": due_date.to_notion() })
+}
 
-        Ok(tasks)
-    }
+fn tags_value(tags: &[String]) -> serde_json::Value {
+    serde_json::json!({
+        "multi_select": tags.iter().map(|tag| serde_json::json!({ "name": tag })).collect::<Vec<_>>()
+    })
+}
 
-    pub async fn update_task_status(&self, task_id: &str, status: TaskStatus) -> Result<Task> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let body = serde_json::json!({
-            "properties": {
-                "Status": {
-                    "status": {
-                        "name": status.to_string()
-                    }
-                }
-            }
-        });
+/// Splits a comma-separated tag list, trimming whitespace and dropping
+/// empty entries.
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
 
-        let response = self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+/// A task to be created with a single `POST /v1/pages` request.
+///
+/// Only the title is required; every other property is left out of the
+/// request unless it is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTask {
+    pub title: String,
+    /// Left out of the request when `None`, so that Notion gives the page
+    /// the default status of the database.
+    pub status: Option<TaskStatus>,
+    pub priority: Option<TaskPriority>,
+    pub due_date: Option<DueDate>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+    /// Raw property values keyed by property name, sent as-is for database
+    /// columns the CLI has no dedicated field for.
+    pub extra_properties: serde_json::Map<String, serde_json::Value>,
+}
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status,
-            url: response["url"].as_str().map(|s| s.to_string()),
+impl NewTask {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            status: None,
             priority: None,
             due_date: None,
             tags: Vec::new(),
             description: None,
-        };
+            extra_properties: serde_json::Map::new(),
+        }
+    }
 
-        Ok(task)
+    pub fn status(mut self, status: TaskStatus) -> Self {
+        self.status = Some(status);
+        self
     }
 
-    pub async fn set_task_priority(&self, task_id: &str, priority: TaskPriority) -> Result<Task> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let body = serde_json::json!({
-            "properties": {
-                "Priority": {
-                    "select": {
-                        "name": priority.to_string()
-                    }
-                }
-            }
-        });
+    pub fn priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
 
-        let response = self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    pub fn due_date(mut self, due_date: impl Into<DueDate>) -> Self {
+        self.due_date = Some(due_date.into());
+        self
+    }
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status: TaskStatus::NotStarted,
-            url: response["url"].as_str().map(|s| s.to_string()),
-            priority: Some(priority),
-            due_date: None,
-            tags: Vec::new(),
-            description: None,
-        };
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
 
-        Ok(task)
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
     }
 
-    pub async fn set_task_due_date(&self, task_id: &str, due_date: &str) -> Result<Task> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let body = serde_json::json!({
-            "properties": {
-                "Due Date": {
-                    "date": {
-                        "start": due_date
-                    }
-                }
-            }
-        });
+    /// Sets a property by name using a raw Notion property value, e.g.
+    /// `serde_json::json!({ "number": 3 })`.
+    pub fn property(mut self, name: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra_properties.insert(name.into(), value);
+        self
+    }
 
-        let response = self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    /// The task this would create, without the fields Notion assigns
+    /// (ID, URL, timestamps). Without a status, `default_status` stands in
+    /// for the one Notion would pick.
+    pub fn preview(&self, default_status: TaskStatus) -> Task {
+        Task {
+            id: String::new(),
+            title: self.title.clone(),
+            status: self.status.clone().unwrap_or(default_status),
+            status_color: None,
+            status_group: None,
+            url: None,
+            priority: self.priority.clone(),
+            priority_color: None,
+            due_date: self.due_date,
+            tags: self.tags.clone(),
+            description: self.description.clone(),
+            created_time: None,
+            last_edited_time: None,
+            created_by: None,
+            last_edited_by: None,
+        }
+    }
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status: TaskStatus::NotStarted,
-            url: response["url"].as_str().map(|s| s.to_string()),
-            priority: None,
-            due_date: Some(due_date.to_string()),
-            tags: Vec::new(),
-            description: None,
-        };
+    /// Builds the `properties` object of the create-page request, using the
+    /// property names from `map`.
+    pub fn properties_json(&self, map: &PropertyMap) -> serde_json::Value {
+        let mut properties = self.extra_properties.clone();
+        properties.insert(map.title.name.clone(), title_value(&self.title));
+        if let Some(status) = &self.status {
+            properties.insert(map.status.name.clone(), option_value(&map.status, &status.to_string()));
+        }
+        if let Some(priority) = &self.priority {
+            properties.insert(map.priority.name.clone(), option_value(&map.priority, &priority.to_string()));
+        }
+        if let Some(due_date) = &self.due_date {
+            properties.insert(map.due_date.name.clone(), date_value(due_date));
+        }
+        if !self.tags.is_empty() {
+            properties.insert(map.tags.name.clone(), tags_value(&self.tags));
+        }
+        if let Some(description) = &self.description {
+            properties.insert(map.description.name.clone(), rich_text_value(description));
+        }
+        serde_json::Value::Object(properties)
+    }
+}
 
-        Ok(task)
+/// A partial update of a task, sent as a single `PATCH /v1/pages/{id}`.
+///
+/// Fields left as `None` are not touched. For optional properties,
+/// `Some(None)` clears the value in Notion.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub priority: Option<Option<TaskPriority>>,
+    pub due_date: Option<Option<DueDate>>,
+    pub tags: Option<Vec<String>>,
+    pub description: Option<Option<String>>,
+}
+
+impl TaskUpdate {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub async fn set_task_description(&self, task_id: &str, description: &str) -> Result<Task> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let body = serde_json::json!({
-            "properties": {
-                "Description": {
-                    "rich_text": [
-                        {
-                            "type": "text",
-                            "text": {
-                                "content": description,
-                                "link": null
-                            }
-                        }
-                    ]
-                }
-            }
-        });
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
 
-        let response = self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    pub fn status(mut self, status: TaskStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status: TaskStatus::NotStarted,
-            url: response["url"].as_str().map(|s| s.to_string()),
-            priority: None,
-            due_date: None,
-            tags: Vec::new(),
-            description: Some(description.to_string()),
-        };
+    pub fn priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = Some(Some(priority));
+        self
+    }
 
-        Ok(task)
+    pub fn clear_priority(mut self) -> Self {
+        self.priority = Some(None);
+        self
     }
 
-    pub async fn add_task_tags(&self, task_id: &str, tags: &str) -> Result<Task> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let tag_list: Vec<String> = tags.split(',')
-            .map(|s| s.trim().to_string())
-            .collect();
+    pub fn due_date(mut self, due_date: impl Into<DueDate>) -> Self {
+        self.due_date = Some(Some(due_date.into()));
+        self
+    }
 
-        let tag_objects: Vec<serde_json::Value> = tag_list.iter()
-            .map(|tag| serde_json::json!({"name": tag}))
-            .collect();
+    pub fn clear_due_date(mut self) -> Self {
+        self.due_date = Some(None);
+        self
+    }
 
-        let body = serde_json::json!({
-            "properties": {
-                "Tags": {
-                    "multi_select": tag_objects
-                }
-            }
-        });
+    /// Replaces every tag on the task with `tags`.
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
 
-        let response = self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+    pub fn clear_tags(mut self) -> Self {
+        self.tags = Some(Vec::new());
+        self
+    }
 
-        let task = Task {
-            id: response["id"].as_str().unwrap_or_default().to_string(),
-            title: response["properties"]["Name"]["title"][0]["text"]["content"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            status: TaskStatus::NotStarted,
-            url: response["url"].as_str().map(|s| s.to_string()),
-            priority: None,
-            due_date: None,
-            tags: tag_list,
-            description: None,
-        };
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(Some(description.into()));
+        self
+    }
 
-        Ok(task)
+    pub fn clear_description(mut self) -> Self {
+        self.description = Some(None);
+        self
     }
 
-    pub async fn delete_task(&self, task_id: &str) -> Result<()> {
-        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
-        let body = serde_json::json!({
-            "archived": true
-        });
+    /// Whether the update would not change anything.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 
-        self.client
-            .patch(&url)
-            .header("Authorization", format!("Bearer {}", self.config.notion_token))
-            .header("Notion-Version", "2022-06-28")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
+    /// `task` with the update applied, as Notion would store it. Option
+    /// colors of changed fields are unknown and left out.
+    pub fn apply_to(&self, task: &Task) -> Task {
+        let mut task = task.clone();
+        if let Some(title) = &self.title {
+            task.title = title.clone();
+        }
+        if let Some(status) = &self.status {
+            task.status = status.clone();
+            task.status_color = None;
+            task.status_group = None;
+        }
+        if let Some(priority) = &self.priority {
+            task.priority = priority.clone();
+            task.priority_color = None;
+        }
+        if let Some(due_date) = &self.due_date {
+            task.due_date = *due_date;
+        }
+        if let Some(tags) = &self.tags {
+            task.tags = tags.clone();
+        }
+        if let Some(description) = &self.description {
+            task.description = description.clone();
+        }
+        task
+    }
 
-        Ok(())
+    /// Builds the `properties` object of the update request, with only the
+    /// fields that are set, using the property names from `map`.
+    pub fn properties_json(&self, map: &PropertyMap) -> serde_json::Value {
+        let mut properties = serde_json::Map::new();
+        if let Some(title) = &self.title {
+            properties.insert(map.title.name.clone(), title_value(title));
+        }
+        if let Some(status) = &self.status {
+            properties.insert(map.status.name.clone(), option_value(&map.status, &status.to_string()));
+        }
+        if let Some(priority) = &self.priority {
+            let value = match priority {
+                Some(priority) => option_value(&map.priority, &priority.to_string()),
+                None => typed_value(map.priority.kind, serde_json::Value::Null),
+            };
+            properties.insert(map.priority.name.clone(), value);
+        }
+        if let Some(due_date) = &self.due_date {
+            let value = match due_date {
+                Some(due_date) => date_value(due_date),
+                None => serde_json::json!({ "date": null }),
+            };
+            properties.insert(map.due_date.name.clone(), value);
+        }
+        if let Some(tags) = &self.tags {
+            properties.insert(map.tags.name.clone(), tags_value(tags));
+        }
+        if let Some(description) = &self.description {
+            let value = match description {
+                Some(description) => rich_text_value(description),
+                None => serde_json::json!({ "rich_text": [] }),
+            };
+            properties.insert(map.description.name.clone(), value);
+        }
+        serde_json::Value::Object(properties)
+    }
+}
+
+/// A condition on the `Due Date` property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DateCondition {
+    Equals(String),
+    Before(String),
+    After(String),
+    OnOrBefore(String),
+    OnOrAfter(String),
+    IsEmpty,
+    IsNotEmpty,
+}
+
+impl DateCondition {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            DateCondition::Equals(date) => serde_json::json!({ "equals": date }),
+            DateCondition::Before(date) => serde_json::json!({ "before": date }),
+            DateCondition::After(date) => serde_json::json!({ "after": date }),
+            DateCondition::OnOrBefore(date) => serde_json::json!({ "on_or_before": date }),
+            DateCondition::OnOrAfter(date) => serde_json::json!({ "on_or_after": date }),
+            DateCondition::IsEmpty => serde_json::json!({ "is_empty": true }),
+            DateCondition::IsNotEmpty => serde_json::json!({ "is_not_empty": true }),
+        }
+    }
+}
+
+/// A filter over task properties, serialized into the `filter` object of a
+/// Notion database query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskFilter {
+    And(Vec<TaskFilter>),
+    Or(Vec<TaskFilter>),
+    NameContains(String),
+    StatusEquals(TaskStatus),
+    StatusNotEquals(TaskStatus),
+    PriorityEquals(TaskPriority),
+    PriorityIsEmpty,
+    TagContains(String),
+    TagNotContains(String),
+    Due(DateCondition),
+}
+
+impl TaskFilter {
+    /// Combines two filters with `and`, flattening nested `and` groups.
+    pub fn and(self, other: TaskFilter) -> TaskFilter {
+        match self {
+            TaskFilter::And(mut filters) => {
+                filters.push(other);
+                TaskFilter::And(filters)
+            }
+            filter => TaskFilter::And(vec![filter, other]),
+        }
+    }
+
+    /// Combines two filters with `or`, flattening nested `or` groups.
+    pub fn or(self, other: TaskFilter) -> TaskFilter {
+        match self {
+            TaskFilter::Or(mut filters) => {
+                filters.push(other);
+                TaskFilter::Or(filters)
+            }
+            filter => TaskFilter::Or(vec![filter, other]),
+        }
+    }
+
+    /// Builds the filter object, using the property names from `map`.
+    pub fn to_json(&self, map: &PropertyMap) -> serde_json::Value {
+        match self {
+            TaskFilter::And(filters) => serde_json::json!({
+                "and": filters.iter().map(|f| f.to_json(map)).collect::<Vec<_>>()
+            }),
+            TaskFilter::Or(filters) => serde_json::json!({
+                "or": filters.iter().map(|f| f.to_json(map)).collect::<Vec<_>>()
+            }),
+            TaskFilter::NameContains(text) => {
+                property_filter(&map.title, serde_json::json!({ "contains": text }))
+            }
+            TaskFilter::StatusEquals(status) => {
+                property_filter(&map.status, serde_json::json!({ "equals": status.to_string() }))
+            }
+            TaskFilter::StatusNotEquals(status) => {
+                property_filter(&map.status, serde_json::json!({ "does_not_equal": status.to_string() }))
+            }
+            TaskFilter::PriorityEquals(priority) => {
+                property_filter(&map.priority, serde_json::json!({ "equals": priority.to_string() }))
+            }
+            TaskFilter::PriorityIsEmpty => {
+                property_filter(&map.priority, serde_json::json!({ "is_empty": true }))
+            }
+            TaskFilter::TagContains(tag) => {
+                property_filter(&map.tags, serde_json::json!({ "contains": tag }))
+            }
+            TaskFilter::TagNotContains(tag) => {
+                property_filter(&map.tags, serde_json::json!({ "does_not_contain": tag }))
+            }
+            TaskFilter::Due(condition) => property_filter(&map.due_date, condition.to_json()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortProperty {
+    Name,
+    Status,
+    Priority,
+    DueDate,
+}
+
+impl std::str::FromStr for SortProperty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "title" | "name" => Ok(SortProperty::Name),
+            "status" => Ok(SortProperty::Status),
+            "priority" => Ok(SortProperty::Priority),
+            "due" | "due_date" | "due-date" => Ok(SortProperty::DueDate),
+            other => Err(anyhow::anyhow!(
+                "Unknown sort key \"{}\" (expected title, status, priority or due)",
+                other
+            )),
+        }
+    }
+}
+
+impl SortProperty {
+    fn property_name<'a>(&self, map: &'a PropertyMap) -> &'a str {
+        match self {
+            SortProperty::Name => &map.title.name,
+            SortProperty::Status => &map.status.name,
+            SortProperty::Priority => &map.priority.name,
+            SortProperty::DueDate => &map.due_date.name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl std::str::FromStr for SortDirection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "asc" | "ascending" => Ok(SortDirection::Ascending),
+            "desc" | "descending" => Ok(SortDirection::Descending),
+            other => Err(anyhow::anyhow!("Invalid sort direction \"{}\" (expected asc or desc)", other)),
+        }
+    }
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortDirection::Ascending => write!(f, "ascending"),
+            SortDirection::Descending => write!(f, "descending"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskSort {
+    pub property: SortProperty,
+    pub direction: SortDirection,
+}
+
+/// Parses one sort key: `due`, `due:desc`, or `-due` for descending.
+impl std::str::FromStr for TaskSort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (property, direction) = match s.split_once(':') {
+            Some((property, direction)) => (property, direction.parse()?),
+            None => match s.strip_prefix('-') {
+                Some(property) => (property, SortDirection::Descending),
+                None => (s.strip_prefix('+').unwrap_or(s), SortDirection::Ascending),
+            },
+        };
+        Ok(TaskSort { property: property.parse()?, direction })
+    }
+}
+
+impl TaskSort {
+    pub fn to_json(&self, map: &PropertyMap) -> serde_json::Value {
+        serde_json::json!({
+            "property": self.property.property_name(map),
+            "direction": self.direction.to_string()
+        })
+    }
+}
+
+/// Filter and sort options for a database query. An empty query matches
+/// every task in Notion's default order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    pub filter: Option<TaskFilter>,
+    pub sorts: Vec<TaskSort>,
+}
+
+impl TaskQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a filter, combining it with any existing one using `and`.
+    pub fn filter(mut self, filter: TaskFilter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    pub fn sort(mut self, property: SortProperty, direction: SortDirection) -> Self {
+        self.sorts.push(TaskSort { property, direction });
+        self
+    }
+
+    /// Builds the database query body, leaving out empty `filter` and `sorts`.
+    pub fn to_json(&self, map: &PropertyMap) -> serde_json::Value {
+        let mut body = serde_json::json!({});
+        if let Some(filter) = &self.filter {
+            body["filter"] = filter.to_json(map);
+        }
+        if !self.sorts.is_empty() {
+            body["sorts"] = self.sorts.iter().map(|sort| sort.to_json(map)).collect();
+        }
+        body
+    }
+}
+
+/// The fixed groups Notion sorts `status` options into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusGroup {
+    ToDo,
+    InProgress,
+    Complete,
+}
+
+impl StatusGroup {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "to-do" | "to do" | "todo" => Some(StatusGroup::ToDo),
+            "in progress" => Some(StatusGroup::InProgress),
+            "complete" | "completed" => Some(StatusGroup::Complete),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StatusGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusGroup::ToDo => write!(f, "To-do"),
+            StatusGroup::InProgress => write!(f, "In progress"),
+            StatusGroup::Complete => write!(f, "Complete"),
+        }
+    }
+}
+
+/// An option of a `select`, `multi_select` or `status` property.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectOption {
+    pub id: Option<String>,
+    pub name: String,
+    pub color: Option<String>,
+    /// The group of a `status` option; `None` for other property types.
+    pub group: Option<StatusGroup>,
+}
+
+/// A property of the database, as described by its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertySchema {
+    pub id: String,
+    pub name: String,
+    /// The Notion property type, e.g. `status` or `formula`.
+    pub kind: String,
+    /// The options of `select`, `multi_select` and `status` properties.
+    pub options: Vec<SelectOption>,
+}
+
+/// The title and properties of a database, from `GET /v1/databases/{id}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseSchema {
+    pub id: String,
+    pub title: String,
+    /// Every property, in name order.
+    pub properties: Vec<PropertySchema>,
+}
+
+impl DatabaseSchema {
+    pub fn from_json(database: &serde_json::Value) -> Self {
+        let properties = database["properties"]
+            .as_object()
+            .map(|properties| {
+                properties.iter()
+                    .map(|(name, property)| {
+                        let kind = property["type"].as_str().unwrap_or_default().to_string();
+                        let groups = property[kind.as_str()]["groups"].as_array();
+                        let group_of = |option_id: Option<&str>| {
+                            let option_id = option_id?;
+                            groups?.iter()
+                                .find(|group| {
+                                    group["option_ids"]
+                                        .as_array()
+                                        .is_some_and(|ids| ids.iter().any(|id| id.as_str() == Some(option_id)))
+                                })
+                                .and_then(|group| group["name"].as_str())
+                                .and_then(StatusGroup::from_name)
+                        };
+                        let options = property[kind.as_str()]["options"]
+                            .as_array()
+                            .map(|options| {
+                                options.iter()
+                                    .map(|option| SelectOption {
+                                        id: option["id"].as_str().map(|s| s.to_string()),
+                                        name: option["name"].as_str().unwrap_or_default().to_string(),
+                                        color: option["color"].as_str().map(|s| s.to_string()),
+                                        group: group_of(option["id"].as_str()),
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+
+                        PropertySchema {
+                            id: property["id"].as_str().unwrap_or_default().to_string(),
+                            name: name.clone(),
+                            kind,
+                            options,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            id: database["id"].as_str().unwrap_or_default().to_string(),
+            title: rich_text_to_string(&database["title"]),
+            properties,
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&PropertySchema> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    /// Whether `mapped` names a property of this database with the mapped type.
+    pub fn fits(&self, mapped: &PropertyRef) -> bool {
+        self.property(&mapped.name).is_some_and(|p| p.kind == mapped.kind.as_str())
+    }
+
+    /// The properties whose type `field` can be mapped to.
+    pub fn candidates(&self, field: &str) -> Vec<&PropertySchema> {
+        let allowed = PropertyMap::allowed_kinds(field);
+        self.properties
+            .iter()
+            .filter(|p| p.kind.parse::<PropertyKind>().is_ok_and(|kind| allowed.contains(&kind)))
+            .collect()
+    }
+
+    /// Repairs the obvious mismatches of `map`: the title goes to the
+    /// database's title property, and a field whose property exists under
+    /// the same name (ignoring case) with another allowed type is remapped
+    /// to it. Fields without such a match are left unchanged.
+    pub fn suggest_mapping(&self, map: &PropertyMap) -> PropertyMap {
+        let mut suggested = map.clone();
+        for (field, mapped) in map.fields() {
+            if self.fits(mapped) {
+                continue;
+            }
+
+            let found = self.candidates(field).into_iter().find(|p| {
+                field == "title" || p.name.eq_ignore_ascii_case(&mapped.name)
+            });
+            if let Some(property) = found {
+                if let Ok(kind) = property.kind.parse() {
+                    let _ = suggested.set(field, PropertyRef::new(property.name.clone(), kind));
+                }
+            }
+        }
+        suggested
+    }
+
+    /// The options of the property mapped to `mapped`, or an empty list
+    /// when it is missing from the database.
+    pub fn options(&self, mapped: &PropertyRef) -> &[SelectOption] {
+        self.property(&mapped.name).map_or(&[], |p| p.options.as_slice())
+    }
+
+    /// Finds the option named `input`, ignoring case, or fails with the
+    /// list of valid names.
+    fn find_option<'a>(&'a self, mapped: &PropertyRef, input: &str, what: &str) -> Result<&'a SelectOption> {
+        let options = self.options(mapped);
+        options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(input.trim()))
+            .ok_or_else(|| {
+                let valid = options.iter().map(|o| o.name.as_str()).collect::<Vec<_>>().join(", ");
+                anyhow::anyhow!("Invalid {} \"{}\". Valid values: {}", what, input, valid)
+            })
+    }
+
+    /// Validates a status typed by the user against the database options.
+    pub fn resolve_status(&self, map: &PropertyMap, input: &str) -> Result<TaskStatus> {
+        let option = self.find_option(&map.status, input, "status")?;
+        Ok(TaskStatus::from_name(&option.name))
+    }
+
+    /// Validates a priority typed by the user against the database options.
+    pub fn resolve_priority(&self, map: &PropertyMap, input: &str) -> Result<TaskPriority> {
+        let option = self.find_option(&map.priority, input, "priority")?;
+        Ok(TaskPriority::from_name(&option.name))
+    }
+
+    /// The group of the `status` option, `None` for select properties and
+    /// unknown options.
+    pub fn status_group(&self, map: &PropertyMap, status: &TaskStatus) -> Option<StatusGroup> {
+        self.options(&map.status)
+            .iter()
+            .find(|option| TaskStatus::from_name(&option.name) == *status)
+            .and_then(|option| option.group)
+    }
+
+    /// The status options belonging to `group`.
+    pub fn statuses_in_group(&self, map: &PropertyMap, group: StatusGroup) -> Vec<TaskStatus> {
+        self.options(&map.status)
+            .iter()
+            .filter(|option| option.group == Some(group))
+            .map(|option| TaskStatus::from_name(&option.name))
+            .collect()
+    }
+
+    /// A filter excluding finished tasks: those whose status is in the
+    /// Complete group, or `Done` when the property has no groups.
+    pub fn open_filter(&self, map: &PropertyMap) -> TaskFilter {
+        let mut finished = self.statuses_in_group(map, StatusGroup::Complete);
+        if finished.is_empty() {
+            finished.push(TaskStatus::Done);
+        }
+        TaskFilter::And(finished.into_iter().map(TaskFilter::StatusNotEquals).collect())
+    }
+
+    /// Builds a filter for a status name or, for `status` properties, a
+    /// status group name such as "Complete".
+    pub fn status_filter(&self, map: &PropertyMap, input: &str) -> Result<TaskFilter> {
+        if let Ok(status) = self.resolve_status(map, input) {
+            return Ok(TaskFilter::StatusEquals(status));
+        }
+
+        match StatusGroup::from_name(input.trim()) {
+            Some(group) => {
+                let statuses = self.statuses_in_group(map, group);
+                if statuses.is_empty() {
+                    return Err(anyhow::anyhow!("No status belongs to the {} group", group));
+                }
+                Ok(TaskFilter::Or(statuses.into_iter().map(TaskFilter::StatusEquals).collect()))
+            }
+            None => self.resolve_status(map, input).map(TaskFilter::StatusEquals),
+        }
+    }
+
+    /// Describes every mapped field whose property is missing from the
+    /// database or has a different type. Empty when the mapping fits.
+    pub fn mapping_problems(&self, map: &PropertyMap) -> Vec<String> {
+        map.fields()
+            .iter()
+            .filter_map(|(field, mapped)| match self.property(&mapped.name) {
+                None => Some(format!(
+                    "{} is mapped to \"{}\", which does not exist in the database",
+                    field, mapped.name
+                )),
+                Some(property) if property.kind != mapped.kind.as_str() => Some(format!(
+                    "{} is mapped to \"{}\" as {}, but the property has type {}",
+                    field, mapped.name, mapped.kind, property.kind
+                )),
+                Some(_) => None,
+            })
+            .collect()
+    }
+
+    /// Fails with a description of every mapping problem and the available
+    /// properties when the mapping does not fit this database.
+    pub fn check_mapping(&self, map: &PropertyMap) -> Result<()> {
+        let problems = self.mapping_problems(map);
+        if problems.is_empty() {
+            return Ok(());
+        }
+
+        let available = self.properties
+            .iter()
+            .map(|p| format!("{} ({})", p.name, p.kind))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(anyhow::anyhow!(
+            "The property mapping does not match database \"{}\":\n  - {}\nAvailable properties: {}",
+            self.title,
+            problems.join("\n  - "),
+            available
+        ))
+    }
+}
+
+/// The bot user an integration token belongs to, from `GET /v1/users/me`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BotUser {
+    pub id: String,
+    pub name: Option<String>,
+    /// The workspace the integration was installed in.
+    pub workspace_name: Option<String>,
+}
+
+/// Definition sent to Notion to add a property of type `kind`, with
+/// `options` for select-like properties.
+fn property_definition(kind: PropertyKind, options: &[&str]) -> serde_json::Value {
+    let config = match kind {
+        PropertyKind::Select | PropertyKind::MultiSelect | PropertyKind::Status if !options.is_empty() => {
+            serde_json::json!({ "options": options.iter().map(|name| serde_json::json!({ "name": name })).collect::<Vec<_>>() })
+        }
+        _ => serde_json::json!({}),
+    };
+    serde_json::json!({ kind.as_str(): config })
+}
+
+/// Whether sending `method` to `url` twice has the same effect as once.
+/// Of the POST endpoints, database queries and search only read; creating a
+/// page does not.
+fn is_idempotent(method: &Method, url: &str) -> bool {
+    *method != Method::POST || url.ends_with("/query") || url.ends_with("/search")
+}
+
+/// Largest page size accepted by the Notion database query endpoint.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// One page of results from a database query, with the cursor for the next
+/// page when Notion reports `has_more`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub next_cursor: Option<String>,
+}
+
+#[allow(dead_code)]
+pub struct NotionClient {
+    client: Client,
+    config: Config,
+    api_url: String,
+    page_size: Option<u32>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    schema: OnceCell<DatabaseSchema>,
+    /// Requests held back in dry-run mode; `None` when requests are sent.
+    planned: Option<Arc<Mutex<Vec<PlannedRequest>>>>,
+}
+
+#[allow(dead_code)]
+impl NotionClient {
+    pub fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            config,
+            client: reqwest::Client::new(),
+            api_url: "https://api.notion.com".to_string(),
+            page_size: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: Some(RateLimiter::notion_default()),
+            schema: OnceCell::new(),
+            planned: None,
+        })
+    }
+
+    pub fn new_with_base_url(config: Config, base_url: String) -> Result<Self> {
+        Ok(Self {
+            config,
+            client: reqwest::Client::new(),
+            api_url: base_url,
+            page_size: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: Some(RateLimiter::notion_default()),
+            schema: OnceCell::new(),
+            planned: None,
+        })
+    }
+
+    /// Sends an authenticated request to the Notion API and returns the
+    /// parsed JSON body. Non-success responses are turned into a
+    /// [`NotionError`] built from Notion's error object; rate-limited and
+    /// server errors are retried according to the client's [`RetryPolicy`].
+    async fn request(&self, method: Method, url: &str, body: Option<&serde_json::Value>) -> Result<serde_json::Value> {
+        let idempotent = is_idempotent(&method, url);
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            let mut request = self.client
+                .request(method.clone(), url)
+                .header("Authorization", format!("Bearer {}", self.config.notion_token))
+                .header("Notion-Version", "2022-06-28")
+                .header("Content-Type", "application/json");
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let response = match request.send().await {
+                Ok(response) => response,
+                // Nothing reached Notion, so any request can be sent again.
+                Err(err) if err.is_connect() && attempt < self.retry_policy.max_retries => {
+                    let delay = self.retry_policy.delay(attempt, None);
+                    log::debug!("{} {} could not connect ({}), retrying in {:?}", method, url, err, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let status = response.status();
+            if status.is_success() {
+                return Ok(response.json::<serde_json::Value>().await?);
+            }
+
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(Duration::from_secs_f64);
+            let text = response.text().await.unwrap_or_default();
+            let error = NotionError::from_response(status.as_u16(), &text, retry_after);
+
+            // A server error may come after the change was made, so only
+            // requests that are safe to repeat are retried on one.
+            let retryable = match error {
+                NotionError::RateLimited { .. } => true,
+                _ => error.is_retryable() && idempotent,
+            };
+            if !retryable || attempt >= self.retry_policy.max_retries {
+                return Err(error.into());
+            }
+
+            let delay = self.retry_policy.delay(attempt, retry_after);
+            log::debug!("{} {} failed with {}, retrying in {:?}", method, url, error.code(), delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Sends a request whose body refers to database properties. When Notion
+    /// rejects it with `validation_error`, the database schema is checked so
+    /// that a missing or mistyped mapped property is reported by name.
+    async fn property_request(&self, method: Method, url: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        match self.request(method, url, Some(body)).await {
+            Err(err) if matches!(err.downcast_ref::<NotionError>(), Some(NotionError::ValidationError { .. })) => {
+                if let Ok(schema) = self.get_database().await {
+                    schema.check_mapping(&self.config.properties)?;
+                }
+                Err(err)
+            }
+            result => result,
+        }
+    }
+
+    /// Fetches the schema of the configured database.
+    pub async fn get_database(&self) -> Result<DatabaseSchema> {
+        let url = format!("{}/v1/databases/{}", self.api_url, self.config.database_id);
+        let database = self.request(Method::GET, &url, None).await?;
+        Ok(DatabaseSchema::from_json(&database))
+    }
+
+    /// Returns the bot user of the configured token; fails with
+    /// `unauthorized` when the token is invalid.
+    pub async fn me(&self) -> Result<BotUser> {
+        let url = format!("{}/v1/users/me", self.api_url);
+        let user = self.request(Method::GET, &url, None).await?;
+        Ok(BotUser {
+            id: user["id"].as_str().unwrap_or_default().to_string(),
+            name: user["name"].as_str().map(|s| s.to_string()),
+            workspace_name: user["bot"]["workspace_name"].as_str().map(|s| s.to_string()),
+        })
+    }
+
+    /// Lists every database shared with the integration, following
+    /// pagination cursors.
+    pub async fn search_databases(&self) -> Result<Vec<DatabaseSchema>> {
+        let url = format!("{}/v1/search", self.api_url);
+        let mut databases = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut body = serde_json::json!({
+                "filter": { "property": "object", "value": "database" },
+                "page_size": MAX_PAGE_SIZE
+            });
+            if let Some(cursor) = &cursor {
+                body["start_cursor"] = serde_json::json!(cursor);
+            }
+
+            let response = self.request(Method::POST, &url, Some(&body)).await?;
+            if let Some(results) = response["results"].as_array() {
+                databases.extend(results.iter().map(DatabaseSchema::from_json));
+            }
+
+            cursor = if response["has_more"].as_bool().unwrap_or(false) {
+                response["next_cursor"].as_str().map(|s| s.to_string())
+            } else {
+                None
+            };
+            if cursor.is_none() {
+                return Ok(databases);
+            }
+        }
+    }
+
+    /// Adds `property` to the configured database, with `options` for
+    /// select-like types, and returns the updated schema. Notion cannot
+    /// create `status` or second `title` properties through the API.
+    pub async fn create_property(&self, property: &PropertyRef, options: &[&str]) -> Result<DatabaseSchema> {
+        if matches!(property.kind, PropertyKind::Status | PropertyKind::Title) {
+            return Err(anyhow::anyhow!(
+                "{} properties cannot be created through the Notion API; add \"{}\" in Notion instead",
+                property.kind,
+                property.name
+            ));
+        }
+
+        let url = format!("{}/v1/databases/{}", self.api_url, self.config.database_id);
+        let body = serde_json::json!({
+            "properties": { property.name.as_str(): property_definition(property.kind, options) }
+        });
+        if self.is_dry_run() {
+            let mut schema = self.get_database().await?;
+            self.plan(Method::PATCH, &url, body, None, None);
+            schema.properties.push(PropertySchema {
+                id: String::new(),
+                name: property.name.clone(),
+                kind: property.kind.to_string(),
+                options: options
+                    .iter()
+                    .map(|name| SelectOption { id: None, name: name.to_string(), color: None, group: None })
+                    .collect(),
+            });
+            return Ok(schema);
+        }
+
+        let database = self.request(Method::PATCH, &url, Some(&body)).await?;
+        Ok(DatabaseSchema::from_json(&database))
+    }
+
+    /// Renames the option `from` of the select or multi-select property
+    /// `mapped` to `to`. The option keeps its ID and color, and Notion
+    /// updates every page using it.
+    pub async fn rename_option(&self, mapped: &PropertyRef, from: &str, to: &str) -> Result<DatabaseSchema> {
+        let schema = self.get_database().await?;
+        let mut options = schema.options(mapped).to_vec();
+        if options.iter().any(|option| option.name == to) {
+            return Err(anyhow::anyhow!("{} already has an option named \"{}\"", mapped.name, to));
+        }
+        let option = options
+            .iter_mut()
+            .find(|option| option.name == from)
+            .ok_or_else(|| anyhow::anyhow!("{} has no option named \"{}\"", mapped.name, from))?;
+        option.name = to.to_string();
+        self.set_options(schema, mapped, options).await
+    }
+
+    /// Removes the options named `names` from the select or multi-select
+    /// property `mapped`, and from every page still using them.
+    pub async fn remove_options(&self, mapped: &PropertyRef, names: &[String]) -> Result<DatabaseSchema> {
+        let schema = self.get_database().await?;
+        let options = schema.options(mapped).iter().filter(|option| !names.contains(&option.name)).cloned().collect();
+        self.set_options(schema, mapped, options).await
+    }
+
+    /// Replaces the options of `mapped`. Options are sent with their ID so
+    /// that they keep their color; the ones left out are deleted.
+    async fn set_options(&self, mut schema: DatabaseSchema, mapped: &PropertyRef, options: Vec<SelectOption>) -> Result<DatabaseSchema> {
+        let url = format!("{}/v1/databases/{}", self.api_url, self.config.database_id);
+        let definitions = options
+            .iter()
+            .map(|option| match &option.id {
+                Some(id) => serde_json::json!({ "id": id, "name": option.name }),
+                None => serde_json::json!({ "name": option.name }),
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({
+            "properties": { mapped.name.as_str(): { mapped.kind.as_str(): { "options": definitions } } }
+        });
+        if self.is_dry_run() {
+            self.plan(Method::PATCH, &url, body, None, None);
+            if let Some(property) = schema.properties.iter_mut().find(|p| p.name == mapped.name) {
+                property.options = options;
+            }
+            return Ok(schema);
+        }
+
+        let database = self.request(Method::PATCH, &url, Some(&body)).await?;
+        Ok(DatabaseSchema::from_json(&database))
+    }
+
+    /// The database schema, fetched on first use and cached for the lifetime
+    /// of the client.
+    pub async fn schema(&self) -> Result<&DatabaseSchema> {
+        self.schema.get_or_try_init(|| self.get_database()).await
+    }
+
+    /// Parses a returned page. Once the schema is loaded, the task also
+    /// gets the group of its status.
+    fn parse_task(&self, page: &serde_json::Value) -> Result<Task> {
+        let mut task = Task::from_page(page, &self.config.properties)?;
+        if let Some(schema) = self.schema.get() {
+            task.status_group = schema.status_group(&self.config.properties, &task.status);
+        }
+        Ok(task)
+    }
+
+    /// Validates a status name against the options of the database.
+    pub async fn parse_status(&self, input: &str) -> Result<TaskStatus> {
+        self.schema().await?.resolve_status(&self.config.properties, input)
+    }
+
+    /// Validates a priority name against the options of the database.
+    pub async fn parse_priority(&self, input: &str) -> Result<TaskPriority> {
+        self.schema().await?.resolve_priority(&self.config.properties, input)
+    }
+
+    /// Builds a filter matching a status or status group name.
+    pub async fn status_filter(&self, input: &str) -> Result<TaskFilter> {
+        self.schema().await?.status_filter(&self.config.properties, input)
+    }
+
+    /// Builds a filter excluding finished tasks.
+    pub async fn open_filter(&self) -> Result<TaskFilter> {
+        Ok(self.schema().await?.open_filter(&self.config.properties))
+    }
+
+    /// The property mapping used to read and write tasks.
+    pub fn property_map(&self) -> &PropertyMap {
+        &self.config.properties
+    }
+
+    /// The ID of the task database.
+    pub fn database_id(&self) -> &str {
+        &self.config.database_id
+    }
+
+    /// Replaces the policy used to retry rate-limited and server errors.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Throttles every request sent by this client with `rate_limiter`.
+    /// Pass a clone of the same limiter to several clients to share it.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Records every request that would change Notion instead of sending
+    /// it; reads are still sent. Mutating methods return the task as the
+    /// request would leave it. See [`NotionClient::take_planned_requests`].
+    pub fn with_dry_run(mut self) -> Self {
+        self.planned = Some(Arc::new(Mutex::new(Vec::new())));
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.planned.is_some()
+    }
+
+    /// The requests held back in dry-run mode so far, oldest first.
+    pub fn take_planned_requests(&self) -> Vec<PlannedRequest> {
+        match &self.planned {
+            Some(planned) => std::mem::take(&mut *planned.lock().expect("planned requests lock poisoned")),
+            None => Vec::new(),
+        }
+    }
+
+    fn plan(&self, method: Method, url: &str, body: serde_json::Value, before: Option<Task>, after: Option<Task>) {
+        let request = PlannedRequest { method: method.to_string(), url: url.to_string(), body: Some(body), before, after };
+        log::debug!("Dry run, not sending {} {}", request.method, request.url);
+        if let Some(planned) = &self.planned {
+            planned.lock().expect("planned requests lock poisoned").push(request);
+        }
+    }
+
+    /// Sends requests as fast as possible, relying on retries alone when
+    /// Notion answers with `rate_limited`.
+    pub fn without_rate_limiter(mut self) -> Self {
+        self.rate_limiter = None;
+        self
+    }
+
+    /// Sets how many tasks are requested per database query page.
+    /// Values are clamped to Notion's accepted range of 1 to 100.
+    pub fn with_page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size.clamp(1, MAX_PAGE_SIZE));
+        self
+    }
+
+    /// Creates a task with only a title and the "Not started" status.
+    pub async fn add_task(&self, title: &str) -> Result<Task> {
+        self.create_task(&NewTask::new(title)).await
+    }
+
+    /// Creates a task with all of its properties in one request.
+    pub async fn create_task(&self, task: &NewTask) -> Result<Task> {
+        let url = format!("{}/v1/pages", self.api_url);
+        let body = serde_json::json!({
+            "parent": { "database_id": self.config.database_id },
+            "properties": task.properties_json(&self.config.properties)
+        });
+
+        if self.is_dry_run() {
+            // Notion usually starts new pages on the first status of the
+            // To-do group.
+            let default_status = self
+                .schema()
+                .await?
+                .statuses_in_group(&self.config.properties, StatusGroup::ToDo)
+                .into_iter()
+                .next()
+                .unwrap_or(TaskStatus::NotStarted);
+            let task = task.preview(default_status);
+            self.plan(Method::POST, &url, body, None, Some(task.clone()));
+            return Ok(task);
+        }
+
+        let response = self.property_request(Method::POST, &url, &body).await?;
+
+        self.parse_task(&response)
+    }
+
+    /// Fetches a single task by its page ID.
+    pub async fn get_task(&self, task_id: &str) -> Result<Task> {
+        let page = self.get_page(task_id).await?;
+        self.parse_task(&page)
+    }
+
+    /// Fetches a task with every page property, the names of the users who
+    /// created and last edited it, and the page body.
+    pub async fn get_task_details(&self, task_id: &str) -> Result<TaskDetails> {
+        let page = self.get_page(task_id).await?;
+        let task = self.parse_task(&page)?;
+
+        let properties = page["properties"]
+            .as_object()
+            .map(|properties| {
+                properties.iter()
+                    .map(|(name, value)| (name.clone(), property_to_string(value)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let created_by = match &task.created_by {
+            Some(id) => Some(self.user_name(id).await),
+            None => None,
+        };
+        let last_edited_by = match &task.last_edited_by {
+            Some(id) => Some(self.user_name(id).await),
+            None => None,
+        };
+
+        let content = self.get_page_content(task_id).await?;
+
+        Ok(TaskDetails {
+            task,
+            properties,
+            created_by,
+            last_edited_by,
+            content,
+        })
+    }
+
+    async fn get_page(&self, page_id: &str) -> Result<serde_json::Value> {
+        let url = format!("{}/v1/pages/{}", self.api_url, page_id);
+        self.request(Method::GET, &url, None).await
+    }
+
+    /// Looks up a user's name, falling back to the ID when the integration
+    /// lacks the capability to read user information.
+    async fn user_name(&self, user_id: &str) -> String {
+        let url = format!("{}/v1/users/{}", self.api_url, user_id);
+        match self.request(Method::GET, &url, None).await {
+            Ok(user) => user["name"].as_str().unwrap_or(user_id).to_string(),
+            Err(_) => user_id.to_string(),
+        }
+    }
+
+    /// Fetches the top-level blocks of a page body, one line of text per
+    /// block, following pagination cursors.
+    pub async fn get_page_content(&self, page_id: &str) -> Result<Vec<String>> {
+        let mut lines = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut url = format!("{}/v1/blocks/{}/children?page_size={}", self.api_url, page_id, MAX_PAGE_SIZE);
+            if let Some(cursor) = &cursor {
+                url.push_str(&format!("&start_cursor={}", cursor));
+            }
+
+            let response = self.request(Method::GET, &url, None).await?;
+            if let Some(blocks) = response["results"].as_array() {
+                lines.extend(blocks.iter().filter_map(block_to_string));
+            }
+
+            cursor = if response["has_more"].as_bool().unwrap_or(false) {
+                response["next_cursor"].as_str().map(|s| s.to_string())
+            } else {
+                None
+            };
+            if cursor.is_none() {
+                return Ok(lines);
+            }
+        }
+    }
+
+    /// Fetches every task in the database, following pagination cursors
+    /// until Notion reports there are no more results.
+    pub async fn list_tasks(&self) -> Result<Vec<Task>> {
+        self.query_tasks(&TaskQuery::default()).await
+    }
+
+    /// Streams every task in the database, requesting the next page only
+    /// once the tasks of the previous one have been consumed.
+    pub fn stream_tasks(&self) -> impl Stream<Item = Result<Task>> + '_ {
+        self.stream_query(TaskQuery::default())
+    }
+
+    /// Fetches every task matching `query`, with filtering and sorting done
+    /// by Notion.
+    pub async fn query_tasks(&self, query: &TaskQuery) -> Result<Vec<Task>> {
+        self.stream_query(query.clone()).try_collect().await
+    }
+
+    /// Streams every task matching `query`, one page at a time.
+    pub fn stream_query(&self, query: TaskQuery) -> impl Stream<Item = Result<Task>> + '_ {
+        // `None` marks the stream as exhausted, `Some(None)` is the first page.
+        stream::try_unfold(Some(None), move |cursor: Option<Option<String>>| {
+            let query = query.clone();
+            async move {
+                let Some(cursor) = cursor else {
+                    return Ok::<_, anyhow::Error>(None);
+                };
+                let page = self.query_tasks_page(&query, cursor.as_deref()).await?;
+                let next = page.next_cursor.map(Some);
+                Ok(Some((stream::iter(page.tasks.into_iter().map(Ok)), next)))
+            }
+        })
+        .try_flatten()
+    }
+
+    /// Fetches a single page of tasks matching `query`, starting at
+    /// `start_cursor`.
+    pub async fn query_tasks_page(&self, query: &TaskQuery, start_cursor: Option<&str>) -> Result<TaskPage> {
+        let url = format!("{}/v1/databases/{}/query", self.api_url, self.config.database_id);
+        let mut body = query.to_json(&self.config.properties);
+        if let Some(page_size) = self.page_size {
+            body["page_size"] = serde_json::json!(page_size);
+        }
+        if let Some(cursor) = start_cursor {
+            body["start_cursor"] = serde_json::json!(cursor);
+        }
+
+        let response = self.property_request(Method::POST, &url, &body).await?;
+
+        let tasks = response["results"]
+            .as_array()
+            .map(|results| {
+                results.iter()
+                    .map(|page| self.parse_task(page))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let next_cursor = if response["has_more"].as_bool().unwrap_or(false) {
+            response["next_cursor"].as_str().map(|s| s.to_string())
+        } else {
+            None
+        };
+
+        Ok(TaskPage { tasks, next_cursor })
+    }
+
+    /// Applies every field of `update` to the task in one request and
+    /// returns the task as Notion stored it.
+    pub async fn update_task(&self, task_id: &str, update: &TaskUpdate) -> Result<Task> {
+        if update.is_empty() {
+            return Err(anyhow::anyhow!("Nothing to update"));
+        }
+
+        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
+        let body = serde_json::json!({
+            "properties": update.properties_json(&self.config.properties)
+        });
+
+        if self.is_dry_run() {
+            let before = self.get_task(task_id).await?;
+            let after = update.apply_to(&before);
+            self.plan(Method::PATCH, &url, body, Some(before), Some(after.clone()));
+            return Ok(after);
+        }
+
+        let response = self.property_request(Method::PATCH, &url, &body).await?;
+
+        self.parse_task(&response)
+    }
+
+    pub async fn update_task_status(&self, task_id: &str, status: TaskStatus) -> Result<Task> {
+        self.update_task(task_id, &TaskUpdate::new().status(status)).await
+    }
+
+    pub async fn set_task_priority(&self, task_id: &str, priority: TaskPriority) -> Result<Task> {
+        self.update_task(task_id, &TaskUpdate::new().priority(priority)).await
+    }
+
+    /// Sets the due date from any form [`DueDate::parse`] accepts, so an
+    /// invalid date fails before a request is sent.
+    pub async fn set_task_due_date(&self, task_id: &str, due_date: &str) -> Result<Task> {
+        let due_date = DueDate::parse(due_date, None, None)?;
+        self.update_task(task_id, &TaskUpdate::new().due_date(due_date)).await
+    }
+
+    pub async fn set_task_description(&self, task_id: &str, description: &str) -> Result<Task> {
+        self.update_task(task_id, &TaskUpdate::new().description(description)).await
+    }
+
+    /// Adds the comma-separated `tags` to the task, keeping its current
+    /// tags. The task is read first since Notion replaces the whole list.
+    pub async fn add_task_tags(&self, task_id: &str, tags: &str) -> Result<Task> {
+        let task = self.get_task(task_id).await?;
+        let merged = crate::tags::add(&task.tags, &parse_tags(tags));
+        self.update_task(task_id, &TaskUpdate::new().tags(merged)).await
+    }
+
+    /// Removes the comma-separated `tags` from the task, keeping the others.
+    pub async fn remove_task_tags(&self, task_id: &str, tags: &str) -> Result<Task> {
+        let task = self.get_task(task_id).await?;
+        let remaining = crate::tags::remove(&task.tags, &parse_tags(tags));
+        self.update_task(task_id, &TaskUpdate::new().tags(remaining)).await
+    }
+
+    /// Replaces every tag of the task with the comma-separated `tags`; an
+    /// empty list clears them.
+    pub async fn set_task_tags(&self, task_id: &str, tags: &str) -> Result<Task> {
+        self.update_task(task_id, &TaskUpdate::new().tags(parse_tags(tags))).await
+    }
+
+    pub async fn delete_task(&self, task_id: &str) -> Result<()> {
+        let url = format!("{}/v1/pages/{}", self.api_url, task_id);
+        let body = serde_json::json!({
+            "archived": true
+        });
+
+        if self.is_dry_run() {
+            let before = self.get_task(task_id).await?;
+            self.plan(Method::PATCH, &url, body, Some(before), None);
+            return Ok(());
+        }
+
+        self.request(Method::PATCH, &url, Some(&body)).await?;
+
+        Ok(())
     }
 }
 
@@ -494,11 +1938,18 @@ mod tests {
             id: "123".to_string(),
             title: "Test task".to_string(),
             status: TaskStatus::NotStarted,
+            status_color: None,
+            status_group: None,
             url: Some("https://notion.so/123".to_string()),
             priority: None,
+            priority_color: None,
             due_date: None,
             tags: Vec::new(),
             description: None,
+            created_time: None,
+            last_edited_time: None,
+            created_by: None,
+            last_edited_by: None,
         };
 
         assert_eq!(task.id, "123");
@@ -550,4 +2001,331 @@ mod tests {
         assert_eq!(TaskPriority::Medium.symbol(), "🟡");
         assert_eq!(TaskPriority::Low.symbol(), "🟢");
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_empty_query_body() {
+        assert_eq!(TaskQuery::new().to_json(&PropertyMap::default()), serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_query_filter_and_sort_body() {
+        let query = TaskQuery::new()
+            .filter(TaskFilter::StatusEquals(TaskStatus::InProgress))
+            .filter(TaskFilter::Or(vec![
+                TaskFilter::TagContains("work".to_string()),
+                TaskFilter::Due(DateCondition::Before("2024-02-01".to_string())),
+            ]))
+            .sort(SortProperty::DueDate, SortDirection::Ascending);
+
+        assert_eq!(query.to_json(&PropertyMap::default()), serde_json::json!({
+            "filter": {
+                "and": [
+                    { "property": "Status", "status": { "equals": "In progress" } },
+                    {
+                        "or": [
+                            { "property": "Tags", "multi_select": { "contains": "work" } },
+                            { "property": "Due Date", "date": { "before": "2024-02-01" } }
+                        ]
+                    }
+                ]
+            },
+            "sorts": [
+                { "property": "Due Date", "direction": "ascending" }
+            ]
+        }));
+    }
+
+    #[test]
+    fn test_filter_combinators_flatten() {
+        let filter = TaskFilter::NameContains("a".to_string())
+            .and(TaskFilter::PriorityIsEmpty)
+            .and(TaskFilter::Due(DateCondition::IsNotEmpty));
+
+        match filter {
+            TaskFilter::And(filters) => assert_eq!(filters.len(), 3),
+            other => panic!("expected an and filter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_task_from_page_reads_every_property() {
+        let page = serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "url": "https://notion.so/task-id",
+            "created_time": "2024-01-10T09:00:00.000Z",
+            "last_edited_time": "2024-01-12T17:30:00.000Z",
+            "created_by": { "object": "user", "id": "user-1" },
+            "last_edited_by": { "object": "user", "id": "user-2" },
+            "properties": {
+                "Name": { "type": "title", "title": [
+                    { "plain_text": "Write ", "text": { "content": "Write " } },
+                    { "plain_text": "report", "text": { "content": "report" } }
+                ]},
+                "Status": { "type": "status", "status": { "name": "In progress", "color": "blue" } },
+                "Priority": { "type": "select", "select": { "name": "High", "color": "red" } },
+                "Due Date": { "type": "date", "date": { "start": "2024-01-20", "end": null } },
+                "Tags": { "type": "multi_select", "multi_select": [
+                    { "name": "work" }, { "name": "q4" }
+                ]},
+                "Description": { "type": "rich_text", "rich_text": [
+                    { "plain_text": "Quarterly numbers", "text": { "content": "Quarterly numbers" } }
+                ]}
+            }
+        });
+
+        let task = Task::from_page(&page, &PropertyMap::default()).unwrap();
+
+        assert_eq!(task, Task {
+            id: "task-id".to_string(),
+            title: "Write report".to_string(),
+            status: TaskStatus::InProgress,
+            status_color: Some("blue".to_string()),
+            status_group: None,
+            url: Some("https://notion.so/task-id".to_string()),
+            priority: Some(TaskPriority::High),
+            priority_color: Some("red".to_string()),
+            due_date: "2024-01-20".parse().ok(),
+            tags: vec!["work".to_string(), "q4".to_string()],
+            description: Some("Quarterly numbers".to_string()),
+            created_time: Some("2024-01-10T09:00:00.000Z".to_string()),
+            last_edited_time: Some("2024-01-12T17:30:00.000Z".to_string()),
+            created_by: Some("user-1".to_string()),
+            last_edited_by: Some("user-2".to_string()),
+        });
+    }
+
+    #[test]
+    fn test_task_from_page_defaults_missing_properties() {
+        let page = serde_json::json!({ "id": "task-id", "properties": {} });
+
+        let task = Task::from_page(&page, &PropertyMap::default()).unwrap();
+
+        assert_eq!(task.title, "");
+        assert_eq!(task.status, TaskStatus::NotStarted);
+        assert_eq!(task.priority, None);
+        assert!(task.tags.is_empty());
+        assert_eq!(task.description, None);
+    }
+
+    #[test]
+    fn test_task_update_only_sends_set_fields() {
+        let update = TaskUpdate::new()
+            .status(TaskStatus::Done)
+            .clear_due_date()
+            .clear_tags();
+
+        assert_eq!(update.properties_json(&PropertyMap::default()), serde_json::json!({
+            "Status": { "status": { "name": "Done" } },
+            "Due Date": { "date": null },
+            "Tags": { "multi_select": [] }
+        }));
+    }
+
+    #[test]
+    fn test_empty_task_update() {
+        assert!(TaskUpdate::new().is_empty());
+        assert!(!TaskUpdate::new().clear_priority().is_empty());
+    }
+
+    #[test]
+    fn test_property_to_string() {
+        let number = serde_json::json!({ "type": "number", "number": 3 });
+        let checkbox = serde_json::json!({ "type": "checkbox", "checkbox": true });
+        let date = serde_json::json!({ "type": "date", "date": { "start": "2024-01-20", "end": "2024-01-22" } });
+        let formula = serde_json::json!({ "type": "formula", "formula": { "type": "string", "string": "ok" } });
+        let empty_select = serde_json::json!({ "type": "select", "select": null });
+
+        assert_eq!(property_to_string(&number), "3");
+        assert_eq!(property_to_string(&checkbox), "Yes");
+        assert_eq!(property_to_string(&date), "2024-01-20 → 2024-01-22");
+        assert_eq!(property_to_string(&formula), "ok");
+        assert_eq!(property_to_string(&empty_select), "");
+    }
+
+    #[test]
+    fn test_block_to_string() {
+        let todo = serde_json::json!({
+            "type": "to_do",
+            "to_do": { "rich_text": [{ "plain_text": "Ship it" }], "checked": true }
+        });
+        let image = serde_json::json!({ "type": "image", "image": {} });
+
+        assert_eq!(block_to_string(&todo), Some("[x] Ship it".to_string()));
+        assert_eq!(block_to_string(&image), None);
+    }
+
+    #[test]
+    fn test_custom_mapping_is_used_for_reads_and_writes() {
+        let mut map = PropertyMap::default();
+        map.set("status", PropertyRef::new("State", PropertyKind::Select)).unwrap();
+        map.set("due_date", PropertyRef::new("Deadline", PropertyKind::Date)).unwrap();
+
+        let page = serde_json::json!({
+            "id": "task-id",
+            "properties": {
+                "State": { "type": "select", "select": { "name": "Done" } },
+                "Deadline": { "type": "date", "date": { "start": "2024-03-01" } }
+            }
+        });
+        let task = Task::from_page(&page, &map).unwrap();
+        assert_eq!(task.status, TaskStatus::Done);
+        assert_eq!(task.due_date, "2024-03-01".parse().ok());
+
+        let update = TaskUpdate::new().status(TaskStatus::InProgress);
+        assert_eq!(update.properties_json(&map), serde_json::json!({
+            "State": { "select": { "name": "In progress" } }
+        }));
+
+        let filter = TaskFilter::Due(DateCondition::IsEmpty);
+        assert_eq!(filter.to_json(&map), serde_json::json!({
+            "property": "Deadline",
+            "date": { "is_empty": true }
+        }));
+    }
+
+    #[test]
+    fn test_schema_reports_mapping_problems() {
+        let database = serde_json::json!({
+            "id": "database-id",
+            "title": [{ "plain_text": "Tasks" }],
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Status": { "id": "a", "type": "select", "select": { "options": [
+                    { "id": "1", "name": "Not started", "color": "gray" }
+                ]}},
+                "Priority": { "id": "b", "type": "select", "select": { "options": [] } },
+                "Deadline": { "id": "c", "type": "date", "date": {} },
+                "Tags": { "id": "d", "type": "multi_select", "multi_select": { "options": [] } },
+                "Description": { "id": "e", "type": "rich_text", "rich_text": {} }
+            }
+        });
+        let schema = DatabaseSchema::from_json(&database);
+
+        assert_eq!(schema.title, "Tasks");
+        assert_eq!(schema.property("Status").unwrap().options[0].name, "Not started");
+
+        let problems = schema.mapping_problems(&PropertyMap::default());
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("status is mapped to \"Status\" as status"));
+        assert!(problems[1].starts_with("due_date is mapped to \"Due Date\""));
+
+        let err = schema.check_mapping(&PropertyMap::default()).unwrap_err();
+        assert!(err.to_string().contains("Deadline (date)"));
+    }
+
+    #[test]
+    fn test_unknown_options_are_kept_by_name() {
+        assert_eq!(TaskStatus::from_name("Blocked"), TaskStatus::Other("Blocked".to_string()));
+        assert_eq!(TaskStatus::from_name("done"), TaskStatus::Done);
+        assert_eq!(TaskStatus::Other("Blocked".to_string()).to_string(), "Blocked");
+        assert_eq!(TaskPriority::from_name("P0"), TaskPriority::Other("P0".to_string()));
+    }
+
+    #[test]
+    fn test_priorities_order_by_urgency() {
+        let mut priorities = vec![
+            TaskPriority::Medium,
+            TaskPriority::Other("P1".to_string()),
+            TaskPriority::High,
+            TaskPriority::Low,
+            TaskPriority::Other("P0".to_string()),
+        ];
+        priorities.sort();
+        assert_eq!(
+            priorities,
+            vec![
+                TaskPriority::Other("P0".to_string()),
+                TaskPriority::Other("P1".to_string()),
+                TaskPriority::Low,
+                TaskPriority::Medium,
+                TaskPriority::High,
+            ]
+        );
+    }
+
+    fn custom_schema() -> DatabaseSchema {
+        DatabaseSchema::from_json(&serde_json::json!({
+            "id": "database-id",
+            "title": [{ "plain_text": "Tasks" }],
+            "properties": {
+                "Status": { "id": "s", "type": "status", "status": {
+                    "options": [
+                        { "id": "1", "name": "Backlog", "color": "gray" },
+                        { "id": "2", "name": "In review", "color": "purple" },
+                        { "id": "3", "name": "Blocked", "color": "red" },
+                        { "id": "4", "name": "Archived", "color": "default" }
+                    ],
+                    "groups": [
+                        { "name": "To-do", "option_ids": ["1"] },
+                        { "name": "In progress", "option_ids": ["2", "3"] },
+                        { "name": "Complete", "option_ids": ["4"] }
+                    ]
+                }},
+                "Priority": { "id": "p", "type": "select", "select": {
+                    "options": [{ "id": "a", "name": "P0", "color": "red" }, { "id": "b", "name": "P1", "color": "orange" }]
+                }}
+            }
+        }))
+    }
+
+    #[test]
+    fn test_resolve_options_from_schema() {
+        let schema = custom_schema();
+        let map = PropertyMap::default();
+
+        assert_eq!(schema.resolve_status(&map, "in review").unwrap(), TaskStatus::Other("In review".to_string()));
+        assert_eq!(schema.resolve_priority(&map, "p0").unwrap(), TaskPriority::Other("P0".to_string()));
+
+        let err = schema.resolve_status(&map, "Done").unwrap_err();
+        assert_eq!(err.to_string(), "Invalid status \"Done\". Valid values: Backlog, In review, Blocked, Archived");
+    }
+
+    #[test]
+    fn test_status_groups() {
+        let schema = custom_schema();
+        let map = PropertyMap::default();
+
+        assert_eq!(schema.options(&map.status)[2].group, Some(StatusGroup::InProgress));
+        assert_eq!(schema.status_filter(&map, "in progress").unwrap(), TaskFilter::Or(vec![
+            TaskFilter::StatusEquals(TaskStatus::Other("In review".to_string())),
+            TaskFilter::StatusEquals(TaskStatus::Other("Blocked".to_string())),
+        ]));
+        assert_eq!(
+            schema.status_filter(&map, "Blocked").unwrap(),
+            TaskFilter::StatusEquals(TaskStatus::Other("Blocked".to_string()))
+        );
+        assert_eq!(
+            schema.open_filter(&map),
+            TaskFilter::And(vec![TaskFilter::StatusNotEquals(TaskStatus::Other("Archived".to_string()))])
+        );
+    }
+
+    #[test]
+    fn test_suggest_mapping_repairs_obvious_mismatches() {
+        let schema = DatabaseSchema::from_json(&serde_json::json!({
+            "id": "database-id",
+            "title": [{ "plain_text": "Tasks" }],
+            "properties": {
+                "Task": { "id": "t", "type": "title", "title": {} },
+                "status": { "id": "s", "type": "select", "select": { "options": [] } },
+                "Deadline": { "id": "d", "type": "date", "date": {} }
+            }
+        }));
+        let suggested = schema.suggest_mapping(&PropertyMap::default());
+
+        assert_eq!(suggested.title, PropertyRef::new("Task", PropertyKind::Title));
+        assert_eq!(suggested.status, PropertyRef::new("status", PropertyKind::Select));
+        assert_eq!(suggested.due_date, PropertyRef::new("Due Date", PropertyKind::Date));
+        assert_eq!(schema.candidates("due_date").len(), 1);
+    }
+
+    #[test]
+    fn test_property_definition_includes_options() {
+        assert_eq!(
+            property_definition(PropertyKind::Select, &["High", "Low"]),
+            serde_json::json!({ "select": { "options": [{ "name": "High" }, { "name": "Low" }] } })
+        );
+        assert_eq!(property_definition(PropertyKind::Date, &[]), serde_json::json!({ "date": {} }));
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::io::Write;
+
+use crate::notion::Task;
+
+/// How commands print tasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Colored text for people.
+    #[default]
+    Text,
+    /// One JSON object per task, or an array for lists.
+    Json,
+    /// One JSON object per line, written as soon as each task arrives.
+    Ndjson,
+    Yaml,
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    /// Whether tasks are printed in a machine-readable format.
+    pub fn is_machine(&self) -> bool {
+        *self != OutputFormat::Text
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            other => Err(anyhow!(
+                "Invalid output format: {} (expected text, json, ndjson, yaml, csv or tsv)",
+                other
+            )),
+        }
+    }
+}
+
+/// Header of the CSV and TSV formats, in the order of the `Task` fields.
+pub const COLUMNS: [&str; 14] = [
+    "id",
+    "title",
+    "status",
+    "status_color",
+    "url",
+    "priority",
+    "priority_color",
+    "due_date",
+    "tags",
+    "description",
+    "created_time",
+    "last_edited_time",
+    "created_by",
+    "last_edited_by",
+];
+
+/// A task as one CSV row; tags are joined with commas, absent values are
+/// empty.
+fn record(task: &Task) -> [String; 14] {
+    let text = |value: &Option<String>| value.clone().unwrap_or_default();
+    [
+        task.id.clone(),
+        task.title.clone(),
+        task.status.to_string(),
+        text(&task.status_color),
+        text(&task.url),
+        task.priority.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+        text(&task.priority_color),
+        task.due_date.map(|d| d.to_iso()).unwrap_or_default(),
+        task.tags.join(","),
+        text(&task.description),
+        text(&task.created_time),
+        text(&task.last_edited_time),
+        text(&task.created_by),
+        text(&task.last_edited_by),
+    ]
+}
+
+/// Writes a sequence of tasks in a machine-readable format.
+///
+/// NDJSON, CSV and TSV rows are written as tasks arrive; JSON and YAML
+/// documents are written by [`TaskWriter::finish`], since the whole list
+/// forms one value.
+pub struct TaskWriter<W: Write> {
+    format: OutputFormat,
+    sink: Sink<W>,
+    pending: Vec<Task>,
+}
+
+enum Sink<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Plain(W),
+}
+
+impl<W: Write> TaskWriter<W> {
+    pub fn new(format: OutputFormat, out: W) -> Result<Self> {
+        let sink = match format {
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let delimiter = if format == OutputFormat::Csv { b',' } else { b'\t' };
+                let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(out);
+                writer.write_record(COLUMNS)?;
+                Sink::Csv(Box::new(writer))
+            }
+            OutputFormat::Text => return Err(anyhow!("TaskWriter only writes machine-readable formats")),
+            _ => Sink::Plain(out),
+        };
+        Ok(Self { format, sink, pending: Vec::new() })
+    }
+
+    pub fn write(&mut self, task: &Task) -> Result<()> {
+        match (&mut self.sink, self.format) {
+            (Sink::Csv(writer), _) => {
+                writer.write_record(record(task))?;
+                writer.flush()?;
+            }
+            (Sink::Plain(out), OutputFormat::Ndjson) => {
+                serde_json::to_writer(&mut *out, task)?;
+                writeln!(out)?;
+                out.flush()?;
+            }
+            (Sink::Plain(_), _) => self.pending.push(task.clone()),
+        }
+        Ok(())
+    }
+
+    /// Writes what is left (the JSON or YAML document) and flushes the
+    /// output.
+    pub fn finish(self) -> Result<()> {
+        match self.sink {
+            Sink::Csv(mut writer) => writer.flush()?,
+            Sink::Plain(mut out) => {
+                match self.format {
+                    OutputFormat::Json => {
+                        serde_json::to_writer_pretty(&mut out, &self.pending)?;
+                        writeln!(out)?;
+                    }
+                    OutputFormat::Yaml => serde_yaml::to_writer(&mut out, &self.pending)?,
+                    _ => {}
+                }
+                out.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a single task: an object for JSON, NDJSON and YAML, and a header
+/// with one row for CSV and TSV.
+pub fn write_task<W: Write>(format: OutputFormat, task: &Task, mut out: W) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, task)?;
+            writeln!(out)?;
+            out.flush()?;
+            Ok(())
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_writer(&mut out, task)?;
+            out.flush()?;
+            Ok(())
+        }
+        _ => {
+            let mut writer = TaskWriter::new(format, out)?;
+            writer.write(task)?;
+            writer.finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notion::{TaskPriority, TaskStatus};
+
+    fn task() -> Task {
+        Task {
+            status: TaskStatus::InProgress,
+            status_color: Some("blue".to_string()),
+            priority: Some(TaskPriority::Other("P0".to_string())),
+            due_date: "2024-01-20".parse().ok(),
+            tags: vec!["dev".to_string(), "release".to_string()],
+            ..Task::for_test("Write, then ship")
+        }
+    }
+
+    fn render(format: OutputFormat, tasks: &[Task]) -> String {
+        let mut out = Vec::new();
+        let mut writer = TaskWriter::new(format, &mut out).unwrap();
+        for task in tasks {
+            writer.write(task).unwrap();
+        }
+        writer.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json_schema_uses_option_names() {
+        let json = serde_json::to_value(task()).unwrap();
+
+        assert_eq!(json["status"], "In progress");
+        assert_eq!(json["priority"], "P0");
+        assert_eq!(json["tags"], serde_json::json!(["dev", "release"]));
+        assert!(json["description"].is_null());
+        assert_eq!(serde_json::from_value::<Task>(json).unwrap(), task());
+    }
+
+    #[test]
+    fn test_json_list_and_ndjson() {
+        assert_eq!(render(OutputFormat::Json, &[]), "[]\n");
+
+        let ndjson = render(OutputFormat::Ndjson, &[task(), task()]);
+        assert_eq!(ndjson.lines().count(), 2);
+        assert_eq!(serde_json::from_str::<Task>(ndjson.lines().next().unwrap()).unwrap(), task());
+    }
+
+    #[test]
+    fn test_csv_and_tsv_rows() {
+        let csv = render(OutputFormat::Csv, &[task()]);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), COLUMNS.join(","));
+        assert_eq!(lines.next().unwrap(), "abc,\"Write, then ship\",In progress,blue,,P0,,2024-01-20,\"dev,release\",,,,,");
+
+        let tsv = render(OutputFormat::Tsv, &[task()]);
+        assert!(tsv.lines().nth(1).unwrap().starts_with("abc\tWrite, then ship\tIn progress\t"));
+        assert_eq!(render(OutputFormat::Tsv, &[]), format!("{}\n", COLUMNS.join("\t")));
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let yaml = render(OutputFormat::Yaml, &[task()]);
+        assert!(yaml.starts_with("- id: abc\n"));
+        assert_eq!(serde_yaml::from_str::<Vec<Task>>(&yaml).unwrap(), vec![task()]);
+    }
+}
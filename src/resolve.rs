@@ -0,0 +1,329 @@
+use anyhow::{anyhow, Context, Result};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::notion::{NotionClient, Task, TaskFilter, TaskQuery};
+use crate::template::short_id;
+
+/// Aliases are numbers below this; longer inputs are read as ID prefixes.
+const MAX_ALIAS_DIGITS: usize = 3;
+
+/// ID prefixes shorter than this are rejected as too likely to be ambiguous.
+const MIN_PREFIX_LEN: usize = 4;
+
+/// How a task was referred to on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskRef {
+    /// A complete page ID, possibly taken from a Notion URL.
+    Id(String),
+    /// The number `list` showed next to the task, e.g. `3` or `#3`.
+    Alias(usize),
+    /// The start of a page ID.
+    Prefix(String),
+}
+
+impl TaskRef {
+    pub fn parse(input: &str) -> Result<Self> {
+        let input = input.trim();
+
+        let digits = input.strip_prefix('#').unwrap_or(input);
+        if !digits.is_empty() && digits.len() <= MAX_ALIAS_DIGITS && digits.chars().all(|c| c.is_ascii_digit()) {
+            return Ok(TaskRef::Alias(digits.parse()?));
+        }
+
+        let hex = input.replace('-', "").to_lowercase();
+        if hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Ok(TaskRef::Id(format_id(&hex)));
+        }
+
+        if input.contains("notion.so") || input.contains("notion.site") || input.starts_with("http") {
+            return id_from_url(input)
+                .map(TaskRef::Id)
+                .ok_or_else(|| anyhow!("No page ID found in URL {}", input));
+        }
+
+        if hex.len() < MIN_PREFIX_LEN || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "Invalid task ID \"{}\": use a number shown by `list`, at least {} characters of an ID, a full ID or a Notion URL",
+                input,
+                MIN_PREFIX_LEN
+            ));
+        }
+        Ok(TaskRef::Prefix(hex))
+    }
+}
+
+/// Formats 32 hex digits as a dashed UUID.
+fn format_id(hex: &str) -> String {
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Extracts the page ID from a Notion URL such as
+/// `https://www.notion.so/acme/Write-report-1a2b...?pvs=4`: the last 32 hex
+/// digits of the path.
+fn id_from_url(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let last = path.trim_end_matches('/').rsplit('/').next()?;
+    let hex = last.replace('-', "");
+    let hex = hex.get(hex.len().checked_sub(32)?..)?;
+    hex.chars().all(|c| c.is_ascii_hexdigit()).then(|| format_id(&hex.to_lowercase()))
+}
+
+/// The task IDs of the last `list`, in the order they were printed, so that
+/// `3` can stand for the third one.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AliasCache {
+    pub database_id: String,
+    pub tasks: Vec<CachedTask>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedTask {
+    pub id: String,
+    pub title: String,
+}
+
+impl AliasCache {
+    pub fn new(database_id: &str, tasks: &[Task]) -> Self {
+        Self {
+            database_id: database_id.to_string(),
+            tasks: tasks
+                .iter()
+                .map(|task| CachedTask { id: task.id.clone(), title: task.title.clone() })
+                .collect(),
+        }
+    }
+
+    /// `notion-cli-rs/aliases/<account>.json` in the user's cache directory.
+    pub fn path(account: &str) -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("notion-cli-rs").join("aliases").join(format!("{}.json", account)))
+    }
+
+    /// Reads the cache, treating a missing or unreadable file as no cache.
+    pub fn load(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Cannot write alias cache {}", path.display()))
+    }
+
+    /// The ID of task number `alias` (starting at 1).
+    pub fn get(&self, alias: usize) -> Result<&str> {
+        alias
+            .checked_sub(1)
+            .and_then(|i| self.tasks.get(i))
+            .map(|task| task.id.as_str())
+            .ok_or_else(|| {
+                anyhow!(
+                    "There is no task #{} in the last list ({} tasks); run `list` again",
+                    alias,
+                    self.tasks.len()
+                )
+            })
+    }
+}
+
+/// Whether `id` starts with `prefix` (hex digits, without dashes).
+fn id_starts_with(id: &str, prefix: &str) -> bool {
+    id.replace('-', "").to_lowercase().starts_with(prefix)
+}
+
+/// Finds the only candidate whose ID starts with `prefix` (hex digits,
+/// without dashes), or fails listing the matches.
+fn match_prefix<'a>(prefix: &str, candidates: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<String> {
+    let matches = candidates
+        .into_iter()
+        .filter(|(id, _)| id_starts_with(id, prefix))
+        .collect::<Vec<_>>();
+
+    match matches.as_slice() {
+        [(id, _)] => Ok(id.to_string()),
+        [] => Err(anyhow!("No task ID starts with \"{}\"", prefix)),
+        _ => Err(anyhow!(
+            "The ID prefix \"{}\" matches {} tasks; use a longer prefix:\n{}",
+            prefix,
+            matches.len(),
+            list_candidates(matches)
+        )),
+    }
+}
+
+/// One `  <short id>  <title>` line per candidate, for ambiguity errors.
+fn list_candidates<'a>(candidates: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    candidates
+        .into_iter()
+        .map(|(id, title)| format!("  {}  {}", short_id(id), title))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Score of a title equal to the `--match` text.
+const EXACT: u8 = 3;
+
+/// How well `title` matches the words of `text`, higher is better; `None`
+/// when a word is missing. An exact title beats a title starting with the
+/// text, which beats the text anywhere, which beats the words in any order.
+fn title_score(text: &str, title: &str) -> Option<u8> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let title = title.trim().to_lowercase();
+    if !text.split_whitespace().all(|word| title.contains(word)) {
+        return None;
+    }
+    Some(if title == text {
+        EXACT
+    } else if title.starts_with(&text) {
+        2
+    } else if title.contains(&text) {
+        1
+    } else {
+        0
+    })
+}
+
+/// Keeps the tasks whose title contains every word of `text`, best matches
+/// first and shorter titles first among equals.
+pub fn rank_by_title(text: &str, tasks: Vec<Task>) -> Vec<Task> {
+    let mut scored = tasks
+        .into_iter()
+        .filter_map(|task| title_score(text, &task.title).map(|score| (score, task)))
+        .collect::<Vec<_>>();
+    scored.sort_by(|(a, x), (b, y)| b.cmp(a).then(x.title.chars().count().cmp(&y.title.chars().count())));
+    scored.into_iter().map(|(_, task)| task).collect()
+}
+
+/// The error reported when several titles match and the user cannot be
+/// asked to choose.
+pub fn ambiguous_title(text: &str, tasks: &[Task]) -> anyhow::Error {
+    anyhow!(
+        "\"{}\" matches {} tasks; use more words or an ID:\n{}",
+        text,
+        tasks.len(),
+        list_candidates(tasks.iter().map(|t| (t.id.as_str(), t.title.as_str())))
+    )
+}
+
+/// Looks up tasks whose title contains every word of `text`, best match
+/// first. Notion does the filtering (`title contains`, case-insensitive) and
+/// the results are ranked locally. A title equal to `text` is returned
+/// alone, and no match is an error.
+pub async fn find_by_title(client: &NotionClient, text: &str) -> Result<Vec<Task>> {
+    let words = text.split_whitespace().collect::<Vec<_>>();
+    if words.is_empty() {
+        return Err(anyhow!("--match needs some text to look for"));
+    }
+    let query = words
+        .iter()
+        .fold(TaskQuery::new(), |query, word| query.filter(TaskFilter::NameContains(word.to_string())));
+
+    let mut tasks = rank_by_title(text, client.stream_query(query).try_collect().await?);
+    let exact = tasks.iter().filter(|task| title_score(text, &task.title) == Some(EXACT)).count();
+    if tasks.is_empty() {
+        return Err(anyhow!("No task title contains \"{}\"", text));
+    }
+    if exact == 1 {
+        tasks.truncate(1);
+    }
+    Ok(tasks)
+}
+
+/// Turns a task ID prefix, full ID, Notion URL or `list` alias into the full
+/// page ID. Prefixes are matched against every task of the database, so
+/// that one shared with a task missing from the last `list` is rejected.
+pub async fn resolve_task_id(client: &NotionClient, input: &str, aliases: Option<&AliasCache>) -> Result<String> {
+    let aliases = aliases.filter(|cache| cache.database_id == client.database_id());
+    match TaskRef::parse(input)? {
+        TaskRef::Id(id) => Ok(id),
+        TaskRef::Alias(alias) => {
+            let aliases = aliases.ok_or_else(|| anyhow!("Task #{} is unknown: run `list` first to number the tasks", alias))?;
+            Ok(aliases.get(alias)?.to_string())
+        }
+        TaskRef::Prefix(prefix) => {
+            let tasks = client.stream_query(TaskQuery::new()).try_collect::<Vec<_>>().await?;
+            match_prefix(&prefix, tasks.iter().map(|t| (t.id.as_str(), t.title.as_str())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "1a2b3c4d-5e6f-7a8b-9c0d-1e2f3a4b5c6d";
+
+    fn task(title: &str) -> Task {
+        Task { id: ID.to_string(), ..Task::for_test(title) }
+    }
+
+    #[test]
+    fn test_parse_task_refs() {
+        assert_eq!(TaskRef::parse("#3").unwrap(), TaskRef::Alias(3));
+        assert_eq!(TaskRef::parse("12").unwrap(), TaskRef::Alias(12));
+        assert_eq!(TaskRef::parse("1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d").unwrap(), TaskRef::Id(ID.to_string()));
+        assert_eq!(TaskRef::parse(ID).unwrap(), TaskRef::Id(ID.to_string()));
+        assert_eq!(TaskRef::parse("1A2B").unwrap(), TaskRef::Prefix("1a2b".to_string()));
+        assert_eq!(TaskRef::parse("1234").unwrap(), TaskRef::Prefix("1234".to_string()));
+        assert!(TaskRef::parse("1a2").is_err());
+        assert!(TaskRef::parse("report").is_err());
+    }
+
+    #[test]
+    fn test_parse_notion_urls() {
+        let url = "https://www.notion.so/acme/Write-report-1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d?pvs=4";
+        assert_eq!(TaskRef::parse(url).unwrap(), TaskRef::Id(ID.to_string()));
+        assert_eq!(
+            TaskRef::parse("https://notion.so/1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d").unwrap(),
+            TaskRef::Id(ID.to_string())
+        );
+        assert!(TaskRef::parse("https://www.notion.so/acme/Settings").is_err());
+    }
+
+    #[test]
+    fn test_match_prefix() {
+        let candidates = [(ID, "Write report"), ("1a2b9999-0000-0000-0000-000000000000", "Plan sprint")];
+
+        assert_eq!(match_prefix("1a2b3", candidates).unwrap(), ID);
+        let err = match_prefix("1a2b", candidates).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The ID prefix \"1a2b\" matches 2 tasks; use a longer prefix:\n  1a2b3c4d  Write report\n  1a2b9999  Plan sprint"
+        );
+        assert!(match_prefix("ffff", candidates).is_err());
+    }
+
+    #[test]
+    fn test_rank_by_title() {
+        let tasks = vec![
+            task("Review the quarterly report draft"),
+            task("Report bugs"),
+            task("Write report"),
+            task("Report"),
+            task("Plan sprint"),
+        ];
+
+        let titles = rank_by_title("report", tasks.clone()).into_iter().map(|t| t.title).collect::<Vec<_>>();
+        assert_eq!(titles, ["Report", "Report bugs", "Write report", "Review the quarterly report draft"]);
+
+        let titles = rank_by_title("report review", tasks).into_iter().map(|t| t.title).collect::<Vec<_>>();
+        assert_eq!(titles, ["Review the quarterly report draft"]);
+    }
+
+    #[test]
+    fn test_alias_lookup() {
+        let cache = AliasCache {
+            database_id: "db".to_string(),
+            tasks: vec![CachedTask { id: ID.to_string(), title: "Write report".to_string() }],
+        };
+
+        assert_eq!(cache.get(1).unwrap(), ID);
+        assert!(cache.get(0).is_err());
+        assert!(cache.get(2).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use rand::Rng;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How `NotionClient` retries requests that fail with `rate_limited` (429)
+/// or a server error (5xx). Requests that are not safe to repeat, such as
+/// creating a page, are only retried when rate limited or when the
+/// connection failed before anything was sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every following one.
+    pub initial_backoff: Duration,
+    /// Upper bound for the computed backoff.
+    pub max_backoff: Duration,
+    /// Upper bound for a `Retry-After` delay sent by Notion, so that a bad
+    /// header cannot stall the CLI.
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before retry number `attempt` (starting at 0).
+    ///
+    /// A `Retry-After` value sent by Notion always wins, up to
+    /// `max_retry_after`. Otherwise the exponential backoff is jittered
+    /// between half and all of its value so that concurrent callers do not
+    /// retry in lockstep.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_retry_after);
+        }
+
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff);
+        let half = backoff / 2;
+        if half.is_zero() {
+            return backoff;
+        }
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+}
+
+/// A token bucket limiting how many requests are started per second.
+///
+/// Clones share the same bucket, so one limiter can throttle every request
+/// made by a client, including concurrent ones.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    requests_per_second: f64,
+    burst: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_second` on average, with up to `burst` requests
+    /// sent back to back after an idle period.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+            })),
+            requests_per_second: requests_per_second.max(f64::MIN_POSITIVE),
+            burst,
+        }
+    }
+
+    /// Notion's documented average limit of three requests per second.
+    pub fn notion_default() -> Self {
+        Self::new(3.0, 3)
+    }
+
+    /// Waits until a request may be sent.
+    ///
+    /// Tokens are reserved before sleeping, so waiting callers are served in
+    /// the order they arrived.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.requests_per_second).min(self.burst);
+            bucket.last_refill = now;
+            bucket.tokens -= 1.0;
+
+            if bucket.tokens >= 0.0 {
+                None
+            } else {
+                Some(Duration::from_secs_f64(-bucket.tokens / self.requests_per_second))
+            }
+        };
+
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after_overrides_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(2, Some(Duration::from_secs(7))), Duration::from_secs(7));
+        assert_eq!(policy.delay(0, Some(Duration::from_secs(86_400))), policy.max_retry_after);
+    }
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            ..RetryPolicy::default()
+        };
+
+        let first = policy.delay(0, None);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+        let capped = policy.delay(8, None);
+        assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_spaces_requests_after_burst() {
+        let limiter = RateLimiter::new(2.0, 2);
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limiter_is_shared_between_clones() {
+        let limiter = RateLimiter::new(1.0, 1);
+        let other = limiter.clone();
+        let start = Instant::now();
+
+        limiter.acquire().await;
+        other.acquire().await;
+
+        assert!(start.elapsed() >= Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::{self, ConfigFile};
+
+/// Service name of the tokens stored in the system keyring.
+const SERVICE: &str = "notion-cli-rs";
+
+const MAGIC: &[u8; 4] = b"NCT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+/// scrypt cost (log2 of N) of newly encrypted token files, and the highest
+/// cost accepted when reading one, so that a corrupt header cannot make
+/// scrypt allocate gigabytes.
+const SCRYPT_LOG_N: u8 = 15;
+
+/// Where the token of a configuration was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+    /// The `NOTION_TOKEN` environment variable (or a `.env` file).
+    Environment,
+    /// The `token` key of a config file profile, stored in plaintext.
+    ConfigFile,
+    /// The system keyring: the Secret Service (GNOME Keyring, KWallet, ...)
+    /// on Linux, the Keychain on macOS, the Credential Manager on Windows.
+    Keyring,
+    /// A file encrypted with a passphrase.
+    EncryptedFile,
+}
+
+impl TokenSource {
+    /// Whether the token is stored readable by anyone with access to the file.
+    pub fn is_plaintext(&self) -> bool {
+        matches!(self, TokenSource::Environment | TokenSource::ConfigFile)
+    }
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Environment => write!(f, "NOTION_TOKEN environment variable"),
+            TokenSource::ConfigFile => write!(f, "config file (plaintext)"),
+            TokenSource::Keyring => write!(f, "system keyring"),
+            TokenSource::EncryptedFile => write!(f, "encrypted token file"),
+        }
+    }
+}
+
+/// Runs a keyring call on its own thread: the Secret Service client drives
+/// its own async runtime and deadlocks when called from a tokio worker.
+fn on_thread<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    std::thread::spawn(f).join().expect("keyring thread panicked")
+}
+
+/// The keyring entry of `account`. Fails on platforms without a supported
+/// keyring, where the keyring crate falls back to a store that only lives
+/// in memory and would lose the token on exit.
+fn keyring_entry(account: &str) -> Result<keyring::Entry> {
+    let entry = keyring::Entry::new(SERVICE, account)?;
+    if entry.get_credential().is::<keyring::mock::MockCredential>() {
+        return Err(anyhow!("No system keyring is supported on this platform"));
+    }
+    Ok(entry)
+}
+
+/// The token stored in the keyring for `account` (a profile name).
+/// Returns `Ok(None)` when there is none and an error when the Secret
+/// Service cannot be reached.
+pub fn keyring_token(account: &str) -> Result<Option<String>> {
+    let account = account.to_string();
+    on_thread(move || match keyring_entry(&account)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    })
+}
+
+pub fn store_in_keyring(account: &str, token: &str) -> Result<()> {
+    let (account, token) = (account.to_string(), token.to_string());
+    on_thread(move || -> Result<()> { Ok(keyring_entry(&account)?.set_password(&token)?) })
+        .context("Cannot store the token in the system keyring")
+}
+
+/// Removes the keyring entry of `account`; returns whether there was one.
+pub fn delete_from_keyring(account: &str) -> Result<bool> {
+    let account = account.to_string();
+    on_thread(move || match keyring_entry(&account)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e.into()),
+    })
+}
+
+/// `tokens/<account>.enc` next to the config file.
+pub fn token_file_path(account: &str) -> Option<PathBuf> {
+    let config = ConfigFile::default_path()?;
+    Some(config.parent()?.join("tokens").join(format!("{}.enc", account)))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8) -> Result<Key> {
+    let params = scrypt::Params::new(log_n, 8, 1, 32).map_err(|e| anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut key = Key::default();
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| anyhow!("Cannot derive the encryption key: {}", e))?;
+    Ok(key)
+}
+
+fn encrypt_with_cost(token: &str, passphrase: &str, log_n: u8) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt, log_n)?);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), token.as_bytes())
+        .map_err(|_| anyhow!("Cannot encrypt the token"))?;
+
+    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.push(log_n);
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Encrypts `token` with a key derived from `passphrase` (scrypt and
+/// ChaCha20-Poly1305). The salt, nonce and cost are stored in the output.
+pub fn encrypt_token(token: &str, passphrase: &str) -> Result<Vec<u8>> {
+    encrypt_with_cost(token, passphrase, SCRYPT_LOG_N)
+}
+
+pub fn decrypt_token(data: &[u8], passphrase: &str) -> Result<String> {
+    if data.len() <= HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err(anyhow!("Not a notion-cli-rs token file"));
+    }
+    let log_n = data[MAGIC.len()];
+    if log_n > SCRYPT_LOG_N {
+        return Err(anyhow!("Corrupted token file: unsupported scrypt cost {}", log_n));
+    }
+    let salt = &data[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let nonce = &data[HEADER_LEN - NONCE_LEN..HEADER_LEN];
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, salt, log_n)?);
+    let token = cipher
+        .decrypt(Nonce::from_slice(nonce), &data[HEADER_LEN..])
+        .map_err(|_| anyhow!("Wrong passphrase or corrupted token file"))?;
+    String::from_utf8(token).map_err(|_| anyhow!("Corrupted token file"))
+}
+
+/// The passphrase of the encrypted token file: `$NOTION_TOKEN_PASSPHRASE`,
+/// otherwise asked on the terminal (twice when `confirm` is set).
+pub fn passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = env::var("NOTION_TOKEN_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password("Token file passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err(anyhow!("The passphrase cannot be empty"));
+    }
+    if confirm && rpassword::prompt_password("Repeat the passphrase: ")? != passphrase {
+        return Err(anyhow!("The passphrases do not match"));
+    }
+    Ok(passphrase)
+}
+
+/// Encrypts `token` into the token file of `account` and returns its path.
+pub fn store_in_file(account: &str, token: &str, passphrase: &str) -> Result<PathBuf> {
+    let path = token_file_path(account).ok_or_else(|| anyhow!("Cannot determine the config directory; set NOTION_CONFIG"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    }
+    config::write_private(&path, &encrypt_token(token, passphrase)?)
+        .with_context(|| format!("Cannot write token file {}", path.display()))?;
+    Ok(path)
+}
+
+/// Removes the token file of `account`; returns whether there was one.
+pub fn delete_token_file(account: &str) -> Result<bool> {
+    match token_file_path(account) {
+        Some(path) if path.exists() => {
+            fs::remove_file(&path).with_context(|| format!("Cannot remove {}", path.display()))?;
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Looks up the stored token of `account`: first in the keyring, then in
+/// the encrypted token file, asking for its passphrase.
+pub fn stored_token(account: &str) -> Result<Option<(String, TokenSource)>> {
+    match keyring_token(account) {
+        Ok(Some(token)) => return Ok(Some((token, TokenSource::Keyring))),
+        Ok(None) => {}
+        Err(e) => log::debug!("System keyring unavailable: {:#}", e),
+    }
+
+    match token_file_path(account) {
+        Some(path) if path.exists() => {
+            let data = fs::read(&path).with_context(|| format!("Cannot read token file {}", path.display()))?;
+            let token = decrypt_token(&data, &passphrase(false)?)
+                .with_context(|| format!("Cannot decrypt {}", path.display()))?;
+            Ok(Some((token, TokenSource::EncryptedFile)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Shows only the start of a token, e.g. `secret_…`.
+pub fn mask_token(token: &str) -> String {
+    let visible: String = token.chars().take(7).collect();
+    format!("{}…", visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_token_round_trip() {
+        let data = encrypt_with_cost("secret_abc", "hunter2", 4).unwrap();
+
+        assert!(!data.windows(10).any(|w| w == b"secret_abc"));
+        assert_eq!(decrypt_token(&data, "hunter2").unwrap(), "secret_abc");
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let data = encrypt_with_cost("secret_abc", "hunter2", 4).unwrap();
+        let err = decrypt_token(&data, "hunter3").unwrap_err();
+
+        assert_eq!(err.to_string(), "Wrong passphrase or corrupted token file");
+        assert!(decrypt_token(b"plain text", "hunter2").is_err());
+    }
+
+    #[test]
+    fn test_excessive_scrypt_cost_is_rejected() {
+        let mut data = encrypt_with_cost("secret_abc", "hunter2", 4).unwrap();
+        data[MAGIC.len()] = 40;
+
+        let err = decrypt_token(&data, "hunter2").unwrap_err();
+        assert_eq!(err.to_string(), "Corrupted token file: unsupported scrypt cost 40");
+    }
+
+    #[test]
+    fn test_mask_token() {
+        assert_eq!(mask_token("secret_1234567890"), "secret_…");
+    }
+}
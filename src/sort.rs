@@ -0,0 +1,152 @@
+use chrono::{Local, NaiveDate, Utc};
+use std::cmp::Ordering;
+
+use crate::config::PropertyMap;
+use crate::dates::{DateValue, DueDate};
+use crate::notion::{DatabaseSchema, SortDirection, SortProperty, Task, TaskSort, TaskStatus};
+
+/// Whether Notion can apply `sorts` itself. Notion orders select options
+/// by their position in the database rather than by urgency, and puts
+/// tasks without a due date first when sorting descending, so those keys
+/// are sorted locally.
+pub fn notion_can_sort(sorts: &[TaskSort]) -> bool {
+    sorts.iter().all(|sort| match sort.property {
+        SortProperty::Name | SortProperty::Status => true,
+        SortProperty::DueDate => sort.direction == SortDirection::Ascending,
+        SortProperty::Priority => false,
+    })
+}
+
+/// The statuses of the database in the order they are defined, which is
+/// also the order Notion sorts them in.
+pub fn status_order(schema: &DatabaseSchema, map: &PropertyMap) -> Vec<TaskStatus> {
+    let order = schema
+        .options(&map.status)
+        .iter()
+        .map(|option| TaskStatus::from_name(&option.name))
+        .collect::<Vec<_>>();
+    if order.is_empty() {
+        vec![TaskStatus::NotStarted, TaskStatus::InProgress, TaskStatus::Done]
+    } else {
+        order
+    }
+}
+
+/// Sorts `tasks` by each key in turn. Tasks without a priority or due date
+/// go last whatever the direction, and ties keep their current order.
+pub fn sort_tasks(tasks: &mut [Task], sorts: &[TaskSort], status_order: &[TaskStatus]) {
+    tasks.sort_by(|a, b| {
+        sorts
+            .iter()
+            .map(|sort| compare(sort, a, b, status_order))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn compare(sort: &TaskSort, a: &Task, b: &Task, status_order: &[TaskStatus]) -> Ordering {
+    match sort.property {
+        SortProperty::Name => directed(a.title.to_lowercase().cmp(&b.title.to_lowercase()), sort.direction),
+        SortProperty::Status => {
+            let rank = |status: &TaskStatus| status_order.iter().position(|s| s == status).unwrap_or(status_order.len());
+            let ordering = rank(&a.status)
+                .cmp(&rank(&b.status))
+                .then_with(|| a.status.to_string().cmp(&b.status.to_string()));
+            directed(ordering, sort.direction)
+        }
+        SortProperty::Priority => nulls_last(a.priority.as_ref(), b.priority.as_ref(), sort.direction),
+        SortProperty::DueDate => nulls_last(a.due_date.map(due_key), b.due_date.map(due_key), sort.direction),
+    }
+}
+
+/// Orders due dates by the local day they start on; on the same day, dates
+/// without a time come first, then times in order.
+fn due_key(due: DueDate) -> (NaiveDate, Option<chrono::DateTime<Utc>>) {
+    let time = match due.start {
+        DateValue::Day(_) => None,
+        DateValue::Time(time) => Some(time.with_timezone(&Utc)),
+    };
+    (due.date_in(&Local), time)
+}
+
+fn nulls_last<T: Ord>(a: Option<T>, b: Option<T>, direction: SortDirection) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => directed(a.cmp(&b), direction),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn directed(ordering: Ordering, direction: SortDirection) -> Ordering {
+    match direction {
+        SortDirection::Ascending => ordering,
+        SortDirection::Descending => ordering.reverse(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notion::TaskPriority;
+
+    fn task(title: &str, priority: Option<TaskPriority>, due: Option<&str>) -> Task {
+        Task { priority, due_date: due.map(|due| due.parse().unwrap()), ..Task::for_test(title) }
+    }
+
+    /// Sort keys as `--sort` splits them.
+    fn sorts(keys: &str) -> Vec<TaskSort> {
+        keys.split(',').map(|key| key.parse().unwrap()).collect()
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_sort_keys() {
+        assert_eq!(
+            sorts("priority:desc, -due,+Title"),
+            vec![
+                TaskSort { property: SortProperty::Priority, direction: SortDirection::Descending },
+                TaskSort { property: SortProperty::DueDate, direction: SortDirection::Descending },
+                TaskSort { property: SortProperty::Name, direction: SortDirection::Ascending },
+            ]
+        );
+        assert!("size".parse::<TaskSort>().is_err());
+        assert!("due:up".parse::<TaskSort>().is_err());
+        assert!("".parse::<TaskSort>().is_err());
+
+        assert!(notion_can_sort(&sorts("status:desc,due,title:desc")));
+        assert!(!notion_can_sort(&sorts("due:desc")));
+        assert!(!notion_can_sort(&sorts("title,priority")));
+    }
+
+    #[test]
+    fn test_empty_values_sort_last_in_both_directions() {
+        let mut tasks = vec![
+            task("undated", None, None),
+            task("late", None, Some("2024-01-20")),
+            task("early", None, Some("2024-01-10")),
+        ];
+
+        sort_tasks(&mut tasks, &sorts("due"), &[]);
+        assert_eq!(titles(&tasks), vec!["early", "late", "undated"]);
+        sort_tasks(&mut tasks, &sorts("due:desc"), &[]);
+        assert_eq!(titles(&tasks), vec!["late", "early", "undated"]);
+    }
+
+    #[test]
+    fn test_sort_by_several_keys() {
+        let mut tasks = vec![
+            task("b", Some(TaskPriority::Low), Some("2024-01-10")),
+            task("none", None, Some("2024-01-01")),
+            task("c", Some(TaskPriority::High), Some("2024-01-20")),
+            task("A", Some(TaskPriority::High), Some("2024-01-20")),
+            task("d", Some(TaskPriority::High), Some("2024-01-15")),
+        ];
+
+        sort_tasks(&mut tasks, &sorts("priority:desc,due:asc,title"), &[]);
+        assert_eq!(titles(&tasks), vec!["d", "A", "c", "b", "none"]);
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use colored::{Color, Colorize};
+use std::fmt;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::notion::{Task, TaskStatus};
+use crate::template::short_id;
+
+/// A column of the `list` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    /// The number `list` assigns to the task, usable in place of its ID.
+    Num,
+    Id,
+    Title,
+    Status,
+    Priority,
+    Due,
+    Tags,
+    Description,
+    Url,
+    Created,
+    Edited,
+}
+
+/// Columns shown when `--columns` is not given.
+pub const DEFAULT_COLUMNS: [Column; 7] = [
+    Column::Num,
+    Column::Id,
+    Column::Title,
+    Column::Status,
+    Column::Priority,
+    Column::Due,
+    Column::Tags,
+];
+
+/// Columns narrower than this are never shrunk to fit the terminal.
+const MIN_WIDTH: usize = 8;
+
+/// Space between two columns.
+const GAP: &str = "  ";
+
+impl Column {
+    const ALL: [Column; 11] = [
+        Column::Num,
+        Column::Id,
+        Column::Title,
+        Column::Status,
+        Column::Priority,
+        Column::Due,
+        Column::Tags,
+        Column::Description,
+        Column::Url,
+        Column::Created,
+        Column::Edited,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Num => "num",
+            Column::Id => "id",
+            Column::Title => "title",
+            Column::Status => "status",
+            Column::Priority => "priority",
+            Column::Due => "due",
+            Column::Tags => "tags",
+            Column::Description => "description",
+            Column::Url => "url",
+            Column::Created => "created",
+            Column::Edited => "edited",
+        }
+    }
+
+    /// Title and description wrap onto several lines; other columns are
+    /// truncated when the table is too wide.
+    fn wraps(&self) -> bool {
+        matches!(self, Column::Title | Column::Description)
+    }
+
+    fn header(&self) -> String {
+        match self {
+            Column::Num => "#".to_string(),
+            _ => self.name().to_uppercase(),
+        }
+    }
+
+    /// The text and color of the cell of `task`, the `index`-th row.
+    fn cell(&self, index: usize, task: &Task) -> (String, Option<Color>) {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self {
+            Column::Num => ((index + 1).to_string(), None),
+            Column::Id => (short_id(&task.id), Some(Color::BrightBlack)),
+            Column::Title => (task.title.clone(), None),
+            Column::Status => (
+                format!("{} {}", task.status_symbol(), task.status),
+                Some(status_color(task)),
+            ),
+            Column::Priority => match &task.priority {
+                Some(priority) => (
+                    format!("{} {}", priority.symbol(), priority),
+                    task.priority_color.as_deref().map(notion_color),
+                ),
+                None => (String::new(), None),
+            },
+            Column::Due => {
+                let color = if task.is_overdue(Local::now().date_naive()) { Color::Red } else { Color::BrightYellow };
+                (task.due_date.map(|d| d.to_string()).unwrap_or_default(), Some(color))
+            }
+            Column::Tags => (task.tags.join(", "), Some(Color::Blue)),
+            Column::Description => (text(&task.description), None),
+            Column::Url => (text(&task.url), Some(Color::BrightBlue)),
+            Column::Created => (text(&task.created_time), None),
+            Column::Edited => (text(&task.last_edited_time), None),
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let name = match name.as_str() {
+            "#" | "number" => "num",
+            "due_date" | "due-date" => "due",
+            "created_time" => "created",
+            "last_edited_time" | "last_edited" => "edited",
+            other => other,
+        };
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| {
+                let valid = Column::ALL.iter().map(|c| c.name()).collect::<Vec<_>>().join(", ");
+                anyhow!("Invalid column \"{}\". Valid columns: {}", s.trim(), valid)
+            })
+    }
+}
+
+/// Maps a Notion option color to the closest terminal color.
+pub fn notion_color(color: &str) -> Color {
+    match color {
+        "gray" => Color::BrightBlack,
+        "brown" => Color::TrueColor { r: 159, g: 107, b: 83 },
+        "orange" => Color::TrueColor { r: 217, g: 115, b: 13 },
+        "yellow" => Color::Yellow,
+        "green" => Color::Green,
+        "blue" => Color::Blue,
+        "purple" => Color::Magenta,
+        "pink" => Color::BrightMagenta,
+        "red" => Color::Red,
+        _ => Color::White,
+    }
+}
+
+/// The color of a task's status: the option color set in Notion, or a
+/// fixed color for the default statuses when Notion did not send one.
+pub fn status_color(task: &Task) -> Color {
+    match (&task.status_color, &task.status) {
+        (Some(color), _) => notion_color(color),
+        (None, TaskStatus::NotStarted) => Color::Yellow,
+        (None, TaskStatus::InProgress) => Color::Blue,
+        (None, TaskStatus::Done) => Color::Green,
+        (None, TaskStatus::Other(_)) => Color::White,
+    }
+}
+
+/// The width of the terminal stdout is attached to, or `None` when it is
+/// redirected. `$COLUMNS` takes precedence.
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    terminal_size::terminal_size_of(std::io::stdout()).map(|(width, _)| width.0 as usize)
+}
+
+/// Cuts `text` to at most `width` columns, ending with `…` when shortened.
+pub(crate) fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+/// Splits `text` into lines of at most `width` columns, breaking between
+/// words where possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let needed = if line.is_empty() { word.width() } else { line.width() + 1 + word.width() };
+            if needed <= width {
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // Words longer than the column are broken anywhere.
+            for c in word.chars() {
+                if line.width() + c.width().unwrap_or(0) > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push(c);
+            }
+        }
+        lines.push(line);
+    }
+
+    if lines.is_empty() {
+        lines.push(String::new());
+    }
+    lines
+}
+
+/// Shrinks the widest columns, wrapped ones first, until the table fits in
+/// `max_width`. Columns are never made narrower than their header or
+/// `MIN_WIDTH`, so very narrow terminals still overflow.
+fn fit_widths(columns: &[Column], widths: &mut [usize], max_width: usize) {
+    let gaps = GAP.len() * columns.len().saturating_sub(1);
+    let min = (0..columns.len())
+        .map(|i| columns[i].header().len().max(MIN_WIDTH).min(widths[i]))
+        .collect::<Vec<_>>();
+
+    for wrapped_first in [true, false] {
+        loop {
+            let total = widths.iter().sum::<usize>() + gaps;
+            if total <= max_width {
+                return;
+            }
+            let widest = (0..columns.len())
+                .filter(|&i| columns[i].wraps() == wrapped_first || !wrapped_first)
+                .filter(|&i| widths[i] > min[i])
+                .max_by_key(|&i| widths[i]);
+            match widest {
+                Some(i) => widths[i] = widths[i].saturating_sub(total - max_width).max(min[i]),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Renders `tasks` as an aligned table with a header row. With a
+/// `max_width`, title and description wrap and other columns are
+/// truncated so that rows fit.
+pub fn render_tasks(tasks: &[Task], columns: &[Column], max_width: Option<usize>) -> String {
+    render_tasks_from(0, tasks, columns, max_width)
+}
+
+/// [`render_tasks`] for tasks following `skipped` others, so that the `num`
+/// column keeps counting across several tables.
+pub fn render_tasks_from(skipped: usize, tasks: &[Task], columns: &[Column], max_width: Option<usize>) -> String {
+    let rows = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| columns.iter().map(|column| column.cell(skipped + i, task)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let mut widths = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].0.lines().map(|l| l.width()).max().unwrap_or(0))
+                .max()
+                .unwrap_or(0)
+                .max(column.header().len())
+        })
+        .collect::<Vec<_>>();
+    if let Some(max_width) = max_width {
+        fit_widths(columns, &mut widths, max_width);
+    }
+
+    let mut out = String::new();
+    let header = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, &width)| pad(&column.header(), width))
+        .collect::<Vec<_>>()
+        .join(GAP);
+    out.push_str(&header.trim_end().bold().to_string());
+    out.push('\n');
+
+    for row in &rows {
+        let cells = columns
+            .iter()
+            .zip(row)
+            .zip(&widths)
+            .map(|((column, (text, _)), &width)| {
+                if column.wraps() {
+                    wrap(text, width)
+                } else {
+                    vec![truncate(text.lines().next().unwrap_or_default(), width)]
+                }
+            })
+            .collect::<Vec<_>>();
+        let height = cells.iter().map(|lines| lines.len()).max().unwrap_or(1);
+
+        for line in 0..height {
+            let mut parts = Vec::with_capacity(columns.len());
+            for (i, lines) in cells.iter().enumerate() {
+                let text = pad(lines.get(line).map(String::as_str).unwrap_or_default(), widths[i]);
+                parts.push(match row[i].1 {
+                    Some(color) => text.color(color).to_string(),
+                    None => text,
+                });
+            }
+            out.push_str(parts.join(GAP).trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Pads `text` with spaces to `width` display columns.
+pub(crate) fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(title: &str) -> Task {
+        Task {
+            status: TaskStatus::Done,
+            due_date: "2024-01-20".parse().ok(),
+            tags: vec!["dev".to_string(), "ops".to_string()],
+            ..Task::for_test(title)
+        }
+    }
+
+    #[test]
+    fn test_parse_column() {
+        assert_eq!(" title".parse::<Column>().unwrap(), Column::Title);
+        assert_eq!("due_date".parse::<Column>().unwrap(), Column::Due);
+        let err = "owner".parse::<Column>().unwrap_err();
+        assert!(err.to_string().starts_with("Invalid column \"owner\". Valid columns: num, id, title"));
+    }
+
+    #[test]
+    fn test_truncate_and_wrap() {
+        assert_eq!(truncate("release notes", 8), "release…");
+        assert_eq!(truncate("short", 8), "short");
+        assert_eq!(wrap("write the release notes", 10), vec!["write the", "release", "notes"]);
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+    }
+
+    #[test]
+    fn test_table_is_aligned() {
+        colored::control::set_override(false);
+        let table = render_tasks(&[task("Ship"), task("Write the changelog")], &[Column::Title, Column::Due, Column::Tags], None);
+
+        assert_eq!(
+            table,
+            "TITLE                DUE         TAGS\n\
+             Ship                 2024-01-20  dev, ops\n\
+             Write the changelog  2024-01-20  dev, ops\n"
+        );
+    }
+
+    #[test]
+    fn test_rows_are_numbered_with_short_ids() {
+        colored::control::set_override(false);
+        let mut second = task("Ship");
+        second.id = "1a2b3c4d-5e6f-0000-0000-000000000000".to_string();
+        let table = render_tasks(&[task("Plan"), second], &[Column::Num, Column::Id, Column::Title], None);
+
+        assert_eq!(table, "#  ID        TITLE\n1  abc       Plan\n2  1a2b3c4d  Ship\n");
+    }
+
+    #[test]
+    fn test_table_wraps_title_to_fit() {
+        colored::control::set_override(false);
+        let table = render_tasks(&[task("Write the changelog")], &[Column::Title, Column::Due], Some(24));
+
+        assert_eq!(table, "TITLE         DUE\nWrite the     2024-01-20\nchangelog\n");
+        assert!(table.lines().all(|line| line.width() <= 24));
+    }
+
+    #[test]
+    fn test_narrow_terminals_shrink_columns_to_their_minimum() {
+        colored::control::set_override(false);
+        let wide = render_tasks(&[task("Write the changelog")], &DEFAULT_COLUMNS, Some(40));
+        let narrow = render_tasks(&[task("Write the changelog")], &DEFAULT_COLUMNS, Some(20));
+
+        // Neither fits, so both stop at the same minimum widths.
+        assert_eq!(wide, narrow);
+        assert!(wide.starts_with("#  ID   TITLE     STATUS   PRIORITY  DUE       TAGS\n"));
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::notion::{DatabaseSchema, Task};
+
+/// `current` followed by the tags of `added` it does not have yet.
+pub fn add(current: &[String], added: &[String]) -> Vec<String> {
+    let mut tags = current.to_vec();
+    for tag in added {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// `current` without the tags of `removed`.
+pub fn remove(current: &[String], removed: &[String]) -> Vec<String> {
+    current.iter().filter(|tag| !removed.contains(tag)).cloned().collect()
+}
+
+/// `current` with every tag of `from` replaced by `to`, which takes the
+/// place of the first replaced tag and appears only once.
+pub fn replace(current: &[String], from: &[String], to: &str) -> Vec<String> {
+    let mut tags = Vec::with_capacity(current.len());
+    for tag in current {
+        let tag = if from.contains(tag) { to } else { tag.as_str() };
+        if !tags.iter().any(|t: &String| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// A tag option and how many tasks carry it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagUsage {
+    pub name: String,
+    pub color: Option<String>,
+    pub count: usize,
+}
+
+/// Every option of the tags property `property` in `schema`, in schema
+/// order, with the number of `tasks` using it. Tags found on tasks but not
+/// in the schema are listed last.
+pub fn usage(schema: &DatabaseSchema, property: &str, tasks: &[Task]) -> Vec<TagUsage> {
+    let mut usage = schema
+        .properties
+        .iter()
+        .find(|p| p.name == property)
+        .map(|p| {
+            p.options
+                .iter()
+                .map(|option| TagUsage { name: option.name.clone(), color: option.color.clone(), count: 0 })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    for tag in tasks.iter().flat_map(|task| &task.tags) {
+        match usage.iter_mut().find(|u| &u.name == tag) {
+            Some(u) => u.count += 1,
+            None => usage.push(TagUsage { name: tag.clone(), color: None, count: 1 }),
+        }
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notion::{PropertySchema, SelectOption};
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_add_remove_and_replace() {
+        let current = tags(&["dev", "q1", "ops"]);
+
+        assert_eq!(add(&current, &tags(&["ops", "urgent"])), tags(&["dev", "q1", "ops", "urgent"]));
+        assert_eq!(remove(&current, &tags(&["q1", "missing"])), tags(&["dev", "ops"]));
+        assert_eq!(replace(&current, &tags(&["q1"]), "2024-q1"), tags(&["dev", "2024-q1", "ops"]));
+        assert_eq!(replace(&current, &tags(&["dev", "ops"]), "eng"), tags(&["eng", "q1"]));
+        assert_eq!(replace(&current, &tags(&["q1"]), "dev"), tags(&["dev", "ops"]));
+    }
+
+    #[test]
+    fn test_usage_counts_tasks_per_option() {
+        let option = |name: &str| SelectOption { id: None, name: name.to_string(), color: Some("blue".to_string()), group: None };
+        let schema = DatabaseSchema {
+            id: "db".to_string(),
+            title: "Tasks".to_string(),
+            properties: vec![PropertySchema {
+                id: "t".to_string(),
+                name: "Tags".to_string(),
+                kind: "multi_select".to_string(),
+                options: vec![option("dev"), option("unused")],
+            }],
+        };
+        let task = |names: &[&str]| Task { tags: tags(names), ..Task::for_test("Ship") };
+
+        let usage = usage(&schema, "Tags", &[task(&["dev"]), task(&["dev", "new"])]);
+        let counts = usage.iter().map(|u| (u.name.as_str(), u.count)).collect::<Vec<_>>();
+        assert_eq!(counts, [("dev", 2), ("unused", 0), ("new", 1)]);
+    }
+}
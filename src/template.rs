@@ -0,0 +1,329 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use colored::{Color, Colorize};
+
+use crate::notion::Task;
+use crate::table::{notion_color, pad, status_color, truncate};
+
+/// The layout `print_task` used before templates existed.
+pub const DEFAULT_TEMPLATE: &str = "ID: {id}
+Title: {title}
+Status: {status_symbol} {status}
+Priority: {priority_symbol} {priority}
+    URL: {url}
+    Due: {due}
+    Overdue: {overdue}
+    Tags: {tags}
+    Description: {description}";
+
+/// A value a template placeholder can refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    ShortId,
+    Title,
+    Status,
+    StatusSymbol,
+    StatusColor,
+    Url,
+    Priority,
+    PrioritySymbol,
+    PriorityColor,
+    Due,
+    DaysUntilDue,
+    Overdue,
+    Tags,
+    Description,
+    CreatedTime,
+    LastEditedTime,
+    CreatedBy,
+    LastEditedBy,
+}
+
+impl Field {
+    const NAMES: [(&'static str, Field); 20] = [
+        ("id", Field::Id),
+        ("short_id", Field::ShortId),
+        ("title", Field::Title),
+        ("status", Field::Status),
+        ("status_symbol", Field::StatusSymbol),
+        ("status_color", Field::StatusColor),
+        ("url", Field::Url),
+        ("priority", Field::Priority),
+        ("priority_symbol", Field::PrioritySymbol),
+        ("priority_color", Field::PriorityColor),
+        ("due", Field::Due),
+        ("due_date", Field::Due),
+        ("days_until_due", Field::DaysUntilDue),
+        ("overdue", Field::Overdue),
+        ("tags", Field::Tags),
+        ("description", Field::Description),
+        ("created_time", Field::CreatedTime),
+        ("last_edited_time", Field::LastEditedTime),
+        ("created_by", Field::CreatedBy),
+        ("last_edited_by", Field::LastEditedBy),
+    ];
+
+    fn from_name(name: &str) -> Result<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, field)| *field)
+            .ok_or_else(|| {
+                let valid = Self::NAMES.iter().map(|(n, _)| *n).collect::<Vec<_>>().join(", ");
+                anyhow!("Unknown template field {{{}}}. Valid fields: {}", name, valid)
+            })
+    }
+
+    fn value(&self, task: &Task, today: NaiveDate) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self {
+            Field::Id => task.id.clone(),
+            Field::ShortId => short_id(&task.id),
+            Field::Title => task.title.clone(),
+            Field::Status => task.status.to_string(),
+            Field::StatusSymbol => task.status_symbol().to_string(),
+            Field::StatusColor => text(&task.status_color),
+            Field::Url => text(&task.url),
+            Field::Priority => task.priority.as_ref().map(|p| p.to_string()).unwrap_or_default(),
+            Field::PrioritySymbol => task.priority_symbol().to_string(),
+            Field::PriorityColor => text(&task.priority_color),
+            Field::Due => task.due_date.map(|d| d.to_string()).unwrap_or_default(),
+            Field::DaysUntilDue => days_until_due(task, today).map(|d| d.to_string()).unwrap_or_default(),
+            // Counted from the end of a date range.
+            Field::Overdue if task.is_overdue(today) => match task.due_date.map(|due| (today - due.end_date_in(&Local)).num_days()) {
+                Some(1) => "1 day".to_string(),
+                Some(days) => format!("{} days", days),
+                None => String::new(),
+            },
+            Field::Overdue => String::new(),
+            Field::Tags => task.tags.join(", "),
+            Field::Description => text(&task.description),
+            Field::CreatedTime => text(&task.created_time),
+            Field::LastEditedTime => text(&task.last_edited_time),
+            Field::CreatedBy => text(&task.created_by),
+            Field::LastEditedBy => text(&task.last_edited_by),
+        }
+    }
+
+    /// The colors `print_task` has always used for each field, with overdue
+    /// due dates in red.
+    fn style(&self, task: &Task, value: String, today: NaiveDate) -> String {
+        match self {
+            Field::Id | Field::ShortId => value.bright_black().to_string(),
+            Field::Title => value.bold().to_string(),
+            Field::Status | Field::StatusSymbol => value.color(status_color(task)).to_string(),
+            Field::Priority => match task.priority_color.as_deref() {
+                Some(color) => value.color(notion_color(color)).to_string(),
+                None => value,
+            },
+            Field::Url => value.bright_blue().underline().to_string(),
+            Field::Due if task.is_overdue(today) => value.red().to_string(),
+            Field::Due => value.bright_yellow().to_string(),
+            Field::Overdue => value.red().bold().to_string(),
+            Field::Tags => value.color(Color::Blue).to_string(),
+            _ => value,
+        }
+    }
+}
+
+/// The first 8 characters of a page ID, without dashes.
+pub fn short_id(id: &str) -> String {
+    id.chars().filter(|c| *c != '-').take(8).collect()
+}
+
+/// Days from `today` to the task's due date; negative when overdue.
+pub fn days_until_due(task: &Task, today: NaiveDate) -> Option<i64> {
+    let date = task.due_date?.date_in(&Local);
+    Some((date - today).num_days())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field { field: Field, width: Option<usize> },
+}
+
+/// A task layout such as `{status_symbol} {title} ({due})`.
+///
+/// `{field}` is replaced by a task field and `{field:N}` pads or cuts it to
+/// N columns; `{{` and `}}` are literal braces and `\n`/`\t` are newlines
+/// and tabs. A line whose placeholders are all empty is left out, so
+/// optional fields can have a line of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    lines: Vec<Vec<Segment>>,
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let template = template.replace("\\n", "\n").replace("\\t", "\t");
+        let lines = template.split('\n').map(parse_line).collect::<Result<_>>()?;
+        Ok(Self { lines })
+    }
+
+    /// Renders `task`, colored like `print_task` when colors are enabled.
+    pub fn render(&self, task: &Task) -> String {
+        self.render_on(task, chrono::Local::now().date_naive())
+    }
+
+    fn render_on(&self, task: &Task, today: NaiveDate) -> String {
+        let mut out = Vec::new();
+        for line in &self.lines {
+            let mut text = String::new();
+            let mut placeholders = 0;
+            let mut empty = 0;
+
+            for segment in line {
+                match segment {
+                    Segment::Text(literal) => text.push_str(literal),
+                    Segment::Field { field, width } => {
+                        let mut value = field.value(task, today);
+                        placeholders += 1;
+                        if value.is_empty() {
+                            empty += 1;
+                        }
+                        if let Some(width) = width {
+                            value = pad(&truncate(&value, *width), *width);
+                        }
+                        text.push_str(&field.style(task, value, today));
+                    }
+                }
+            }
+
+            if placeholders == 0 || empty < placeholders {
+                out.push(text);
+            }
+        }
+        out.join("\n")
+    }
+}
+
+fn parse_line(line: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(anyhow!("Unclosed {{ in template")),
+                    }
+                }
+
+                let (name, width) = match placeholder.split_once(':') {
+                    Some((name, width)) => {
+                        let width = width
+                            .trim()
+                            .parse::<usize>()
+                            .map_err(|_| anyhow!("Invalid width in {{{}}}", placeholder))?;
+                        (name, Some(width))
+                    }
+                    None => (placeholder.as_str(), None),
+                };
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Field { field: Field::from_name(name.trim())?, width });
+            }
+            '}' => return Err(anyhow!("Unmatched }} in template; use }}}} for a literal brace")),
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notion::{StatusGroup, TaskPriority, TaskStatus};
+
+    fn task() -> Task {
+        Task {
+            id: "1a2b3c4d-5e6f-0000-0000-000000000000".to_string(),
+            status: TaskStatus::InProgress,
+            priority: Some(TaskPriority::High),
+            due_date: "2024-01-20".parse().ok(),
+            tags: vec!["work".to_string()],
+            ..Task::for_test("Write report")
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 1, 17).unwrap()
+    }
+
+    #[test]
+    fn test_render_one_line_template() {
+        colored::control::set_override(false);
+        let template = Template::parse("{status_symbol} {title} ({due}, in {days_until_due} days) [{short_id}]").unwrap();
+
+        assert_eq!(template.render_on(&task(), today()), "🔄 Write report (2024-01-20, in 3 days) [1a2b3c4d]");
+    }
+
+    #[test]
+    fn test_lines_with_only_empty_fields_are_dropped() {
+        colored::control::set_override(false);
+        let template = Template::parse(DEFAULT_TEMPLATE).unwrap();
+
+        assert_eq!(
+            template.render_on(&task(), today()),
+            "ID: 1a2b3c4d-5e6f-0000-0000-000000000000\n\
+             Title: Write report\n\
+             Status: 🔄 In progress\n\
+             Priority: 🔴 High\n    Due: 2024-01-20\n    Tags: work"
+        );
+    }
+
+    #[test]
+    fn test_overdue_tasks_get_an_overdue_line() {
+        colored::control::set_override(false);
+        let template = Template::parse("Due: {due}\nOverdue: {overdue}").unwrap();
+        let late = NaiveDate::from_ymd_opt(2024, 1, 23).unwrap();
+
+        assert_eq!(template.render_on(&task(), late), "Due: 2024-01-20\nOverdue: 3 days");
+        assert_eq!(template.render_on(&task(), today()), "Due: 2024-01-20");
+
+        let done = Task { status: TaskStatus::Done, ..task() };
+        assert_eq!(template.render_on(&done, late), "Due: 2024-01-20");
+        let shipped = Task {
+            status: TaskStatus::Other("Shipped".to_string()),
+            status_group: Some(StatusGroup::Complete),
+            ..task()
+        };
+        assert_eq!(template.render_on(&shipped, late), "Due: 2024-01-20");
+
+        let range = Task { due_date: "2024-01-15/2024-01-21".parse().ok(), ..task() };
+        assert_eq!(template.render_on(&range, late), "Due: 2024-01-15 → 2024-01-21\nOverdue: 2 days");
+        assert_eq!(template.render_on(&range, today()), "Due: 2024-01-15 → 2024-01-21");
+    }
+
+    #[test]
+    fn test_width_escapes_and_errors() {
+        colored::control::set_override(false);
+        let template = Template::parse("{{{title:5}}}\\t{priority}").unwrap();
+        assert_eq!(template.render_on(&task(), today()), "{Writ…}\tHigh");
+
+        let err = Template::parse("{owner}").unwrap_err();
+        assert!(err.to_string().starts_with("Unknown template field {owner}. Valid fields: id, short_id"));
+        assert!(Template::parse("{title").is_err());
+        assert!(Template::parse("{title:wide}").is_err());
+    }
+}
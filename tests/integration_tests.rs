@@ -1,7 +1,14 @@
-use notion_cli_rs::{Config, NotionClient, TaskStatus, TaskPriority};
+use notion_cli_rs::{
+    Config, NewTask, NotionClient, NotionError, PropertyKind, PropertyRef, RetryPolicy, SortDirection,
+    SortProperty, StatusGroup, TaskFilter, TaskPriority, TaskQuery, TaskStatus, TaskUpdate,
+};
+use notion_cli_rs::bulk::{self, BulkAction};
+use notion_cli_rs::dates::DueDate;
+use notion_cli_rs::resolve::{ambiguous_title, find_by_title, resolve_task_id, AliasCache, CachedTask};
 use anyhow::Result;
-use mockito;
+use futures::TryStreamExt;
 use std::sync::Once;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 static INIT: Once = Once::new();
@@ -16,6 +23,7 @@ fn setup_test_client(mock_server: &mockito::Server) -> Result<(NotionClient, Run
     let config = Config {
         notion_token: "test-token".to_string(),
         database_id: "database-id".to_string(),
+        ..Config::default()
     };
 
     // Create a new Runtime for each test
@@ -46,11 +54,6 @@ fn test_add_task() -> Result<()> {
                         }
                     }
                 ]
-            },
-            "Status": {
-                "status": {
-                    "name": "Not started"
-                }
             }
         }
     });
@@ -264,6 +267,19 @@ fn test_add_task_tags() -> Result<()> {
         }
     });
 
+    // The current tags are read first and kept.
+    let _get = mock_server.mock("GET", "/v1/pages/task-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "properties": {
+                "Tags": { "id": "tags", "type": "multi_select", "multi_select": [{ "id": "work-id", "name": "work", "color": "blue" }] }
+            }
+        }).to_string())
+        .create();
+
     println!("Setting up add_task_tags mock...");
     let _mock = mock_server.mock("PATCH", "/v1/pages/task-id")
         .match_header("authorization", "Bearer test-token")
@@ -303,7 +319,7 @@ fn test_add_task_tags() -> Result<()> {
     println!("Client created");
 
     println!("Making add_task_tags request...");
-    rt.block_on(client.add_task_tags("task-id", "work, urgent"))?;
+    rt.block_on(client.add_task_tags("task-id", "urgent, work"))?;
     println!("Request completed successfully");
 
     Ok(())
@@ -432,6 +448,66 @@ fn test_set_task_due_date() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_set_task_due_date_rejects_invalid_dates_locally() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+    let mock = mock_server.mock("PATCH", "/v1/pages/task-id").expect(0).create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let err = rt.block_on(client.set_task_due_date("task-id", "2024-13-40")).unwrap_err();
+
+    assert!(err.to_string().starts_with("Invalid date \"2024-13-40\""));
+    mock.assert();
+    Ok(())
+}
+
+#[test]
+fn test_due_date_ranges_keep_their_time_zone() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let date = serde_json::json!({
+        "start": "2026-11-01T14:00:00",
+        "end": "2026-11-03T18:00:00",
+        "time_zone": "America/New_York"
+    });
+    let _mock = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": { "Due Date": { "date": date } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "properties": {
+                "Due Date": {
+                    "type": "date",
+                    "date": {
+                        "start": "2026-11-01T14:00:00.000-05:00",
+                        "end": "2026-11-03T18:00:00.000-05:00",
+                        "time_zone": "America/New_York"
+                    }
+                }
+            }
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let due = DueDate::parse("2026-11-01T14:00", Some("2026-11-03 18:00"), Some("America/New_York"))?;
+    let task = rt.block_on(client.update_task("task-id", &TaskUpdate::new().due_date(due)))?;
+
+    let due_date = task.due_date.expect("the task has a due date");
+    assert_eq!(due_date, due);
+    assert!(!due_date.is_all_day());
+    assert_eq!(due_date.to_iso(), "2026-11-01T14:00:00-05:00/2026-11-03T18:00:00-05:00");
+    assert_eq!(serde_json::to_value(&task)?["due_date"], "2026-11-01T14:00:00-05:00/2026-11-03T18:00:00-05:00");
+    _mock.assert();
+
+    Ok(())
+}
+
 #[test]
 fn test_set_task_description() -> Result<()> {
     let mut mock_server = mockito::Server::new();
@@ -499,4 +575,1113 @@ fn test_set_task_description() -> Result<()> {
     println!("Request completed successfully");
 
     Ok(())
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_list_tasks_follows_pagination() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = |id: &str, title: &str| serde_json::json!({
+        "object": "page",
+        "id": id,
+        "url": format!("https://notion.so/{}", id),
+        "properties": {
+            "Name": {
+                "type": "title",
+                "title": [{ "type": "text", "text": { "content": title, "link": null } }]
+            },
+            "Status": {
+                "type": "status",
+                "status": { "name": "Not started" }
+            }
+        }
+    });
+
+    let _first = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .match_body(mockito::Matcher::Json(serde_json::json!({ "page_size": 1 })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [page("task-id-1", "Task 1")],
+            "next_cursor": "cursor-2",
+            "has_more": true
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let _second = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "page_size": 1,
+            "start_cursor": "cursor-2"
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [page("task-id-2", "Task 2")],
+            "next_cursor": null,
+            "has_more": false
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let client = client.with_page_size(1);
+
+    let tasks = rt.block_on(client.list_tasks())?;
+
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].title, "Task 1");
+    assert_eq!(tasks[1].title, "Task 2");
+    _first.assert();
+    _second.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_query_tasks_sends_filter_and_sorts() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let expected_body = serde_json::json!({
+        "filter": {
+            "and": [
+                { "property": "Priority", "select": { "equals": "High" } },
+                { "property": "Tags", "multi_select": { "contains": "work" } }
+            ]
+        },
+        "sorts": [
+            { "property": "Due Date", "direction": "ascending" }
+        ]
+    });
+
+    let _mock = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .match_body(mockito::Matcher::Json(expected_body))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [],
+            "next_cursor": null,
+            "has_more": false
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let query = TaskQuery::new()
+        .filter(TaskFilter::PriorityEquals(TaskPriority::High))
+        .filter(TaskFilter::TagContains("work".to_string()))
+        .sort(SortProperty::DueDate, SortDirection::Ascending);
+    let tasks = rt.block_on(client.query_tasks(&query))?;
+
+    assert!(tasks.is_empty());
+    _mock.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_delete_missing_task_returns_object_not_found() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _mock = mock_server.mock("PATCH", "/v1/pages/missing-id")
+        .with_status(404)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 404,
+            "code": "object_not_found",
+            "message": "Could not find page with ID: missing-id."
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let err = rt.block_on(client.delete_task("missing-id")).unwrap_err();
+    let notion_err = err.downcast_ref::<NotionError>().expect("expected a NotionError");
+
+    assert_eq!(notion_err, &NotionError::ObjectNotFound {
+        message: "Could not find page with ID: missing-id.".to_string(),
+    });
+
+    Ok(())
+}
+
+#[test]
+fn test_unauthorized_list_returns_error() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _mock = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(401)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 401,
+            "code": "unauthorized",
+            "message": "API token is invalid."
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let err = rt.block_on(client.list_tasks()).unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<NotionError>(),
+        Some(NotionError::Unauthorized { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_rate_limited_request_is_retried() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _limited = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(429)
+        .with_header("content-type", "application/json")
+        .with_header("retry-after", "0")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 429,
+            "code": "rate_limited",
+            "message": "You have been rate limited."
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let _ok = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [],
+            "next_cursor": null,
+            "has_more": false
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let tasks = rt.block_on(client.list_tasks())?;
+
+    assert!(tasks.is_empty());
+    _limited.assert();
+    _ok.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_server_error_gives_up_after_max_retries() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _mock = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(502)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 502,
+            "code": "bad_gateway",
+            "message": "Bad gateway."
+        }).to_string())
+        .expect(3)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let client = client.with_retry_policy(RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        ..RetryPolicy::default()
+    });
+
+    let err = rt.block_on(client.list_tasks()).unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<NotionError>(),
+        Some(NotionError::ServerError { status: 502, .. })
+    ));
+    _mock.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_create_is_not_retried_on_server_errors() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _mock = mock_server.mock("POST", "/v1/pages")
+        .with_status(502)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 502,
+            "code": "bad_gateway",
+            "message": "Bad gateway."
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let client = client.with_retry_policy(RetryPolicy {
+        max_retries: 2,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(5),
+        ..RetryPolicy::default()
+    });
+
+    let err = rt.block_on(client.create_task(&NewTask::new("Ship"))).unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<NotionError>(),
+        Some(NotionError::ServerError { status: 502, .. })
+    ));
+    _mock.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_update_returns_full_page_state() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _mock = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "url": "https://notion.so/task-id",
+            "properties": {
+                "Name": {
+                    "type": "title",
+                    "title": [{ "type": "text", "text": { "content": "Test task" }, "plain_text": "Test task" }]
+                },
+                "Status": { "type": "status", "status": { "name": "In progress" } },
+                "Priority": { "type": "select", "select": { "name": "High" } },
+                "Due Date": { "type": "date", "date": { "start": "2024-01-20" } },
+                "Tags": { "type": "multi_select", "multi_select": [{ "name": "work" }] }
+            }
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let task = rt.block_on(client.set_task_priority("task-id", TaskPriority::High))?;
+
+    assert_eq!(task.title, "Test task");
+    assert_eq!(task.status, TaskStatus::InProgress);
+    assert_eq!(task.priority, Some(TaskPriority::High));
+    assert_eq!(task.due_date.map(|d| d.to_iso()), Some("2024-01-20".to_string()));
+    assert_eq!(task.tags, vec!["work".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_create_task_sends_all_properties_in_one_request() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let expected_body = serde_json::json!({
+        "parent": { "database_id": "database-id" },
+        "properties": {
+            "Name": {
+                "title": [{ "type": "text", "text": { "content": "Quarterly report", "link": null } }]
+            },
+            "Status": { "status": { "name": "In progress" } },
+            "Priority": { "select": { "name": "High" } },
+            "Due Date": { "date": { "start": "2024-01-20" } },
+            "Tags": { "multi_select": [{ "name": "work" }, { "name": "q4" }] },
+            "Description": {
+                "rich_text": [{ "type": "text", "text": { "content": "For stakeholders", "link": null } }]
+            },
+            "Estimate": { "number": 3 }
+        }
+    });
+
+    let _mock = mock_server.mock("POST", "/v1/pages")
+        .match_body(mockito::Matcher::Json(expected_body))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "properties": {
+                "Name": { "type": "title", "title": [{ "plain_text": "Quarterly report" }] },
+                "Status": { "type": "status", "status": { "name": "In progress" } },
+                "Priority": { "type": "select", "select": { "name": "High" } },
+                "Due Date": { "type": "date", "date": { "start": "2024-01-20" } },
+                "Tags": { "type": "multi_select", "multi_select": [{ "name": "work" }, { "name": "q4" }] },
+                "Description": { "type": "rich_text", "rich_text": [{ "plain_text": "For stakeholders" }] }
+            }
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let new_task = NewTask::new("Quarterly report")
+        .status(TaskStatus::InProgress)
+        .priority(TaskPriority::High)
+        .due_date("2024-01-20".parse::<DueDate>()?)
+        .tags(vec!["work".to_string(), "q4".to_string()])
+        .description("For stakeholders")
+        .property("Estimate", serde_json::json!({ "number": 3 }));
+    let task = rt.block_on(client.create_task(&new_task))?;
+
+    assert_eq!(task.title, "Quarterly report");
+    assert_eq!(task.priority, Some(TaskPriority::High));
+    assert_eq!(task.tags, vec!["work".to_string(), "q4".to_string()]);
+    _mock.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_update_task_merges_fields_into_one_patch() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let expected_body = serde_json::json!({
+        "properties": {
+            "Status": { "status": { "name": "Done" } },
+            "Priority": { "select": { "name": "Low" } },
+            "Due Date": { "date": null }
+        }
+    });
+
+    let _mock = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .match_body(mockito::Matcher::Json(expected_body))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "properties": {
+                "Status": { "type": "status", "status": { "name": "Done" } },
+                "Priority": { "type": "select", "select": { "name": "Low" } },
+                "Due Date": { "type": "date", "date": null }
+            }
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let update = TaskUpdate::new()
+        .status(TaskStatus::Done)
+        .priority(TaskPriority::Low)
+        .clear_due_date();
+    let task = rt.block_on(client.update_task("task-id", &update))?;
+
+    assert_eq!(task.status, TaskStatus::Done);
+    assert_eq!(task.priority, Some(TaskPriority::Low));
+    assert_eq!(task.due_date, None);
+    _mock.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_get_task_details() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _page = mock_server.mock("GET", "/v1/pages/task-id")
+        .match_header("authorization", "Bearer test-token")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "created_time": "2024-01-20T12:00:00.000Z",
+            "last_edited_time": "2024-01-21T08:00:00.000Z",
+            "created_by": { "object": "user", "id": "user-1" },
+            "last_edited_by": { "object": "user", "id": "user-2" },
+            "properties": {
+                "Name": { "type": "title", "title": [{ "plain_text": "Test task" }] },
+                "Status": { "type": "status", "status": { "name": "Done" } },
+                "Estimate": { "type": "number", "number": 5 }
+            }
+        }).to_string())
+        .create();
+
+    let _user = mock_server.mock("GET", "/v1/users/user-1")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({ "object": "user", "id": "user-1", "name": "Ada" }).to_string())
+        .create();
+
+    let _missing_user = mock_server.mock("GET", "/v1/users/user-2")
+        .with_status(403)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 403,
+            "code": "restricted_resource",
+            "message": "Insufficient permissions."
+        }).to_string())
+        .create();
+
+    let _blocks = mock_server.mock("GET", "/v1/blocks/task-id/children")
+        .match_query(mockito::Matcher::Any)
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [
+                { "type": "heading_2", "heading_2": { "rich_text": [{ "plain_text": "Notes" }] } },
+                { "type": "paragraph", "paragraph": { "rich_text": [{ "plain_text": "Call the bank." }] } }
+            ],
+            "next_cursor": null,
+            "has_more": false
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let details = rt.block_on(client.get_task_details("task-id"))?;
+
+    assert_eq!(details.task.title, "Test task");
+    assert_eq!(details.task.status, TaskStatus::Done);
+    assert_eq!(details.task.created_time, Some("2024-01-20T12:00:00.000Z".to_string()));
+    assert!(details.properties.contains(&("Estimate".to_string(), "5".to_string())));
+    assert_eq!(details.created_by, Some("Ada".to_string()));
+    assert_eq!(details.last_edited_by, Some("user-2".to_string()));
+    assert_eq!(details.content, vec!["## Notes".to_string(), "Call the bank.".to_string()]);
+
+    Ok(())
+}
+
+#[test]
+fn test_validation_error_reports_missing_mapped_property() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _patch = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .with_status(400)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 400,
+            "code": "validation_error",
+            "message": "Due Date is not a property that exists."
+        }).to_string())
+        .create();
+
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "title": [{ "plain_text": "Team board" }],
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Status": { "id": "a", "type": "status", "status": { "options": [] } },
+                "Priority": { "id": "b", "type": "select", "select": { "options": [] } },
+                "Deadline": { "id": "c", "type": "date", "date": {} },
+                "Tags": { "id": "d", "type": "multi_select", "multi_select": { "options": [] } },
+                "Description": { "id": "e", "type": "rich_text", "rich_text": {} }
+            }
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let err = rt.block_on(client.set_task_due_date("task-id", "2024-01-20")).unwrap_err();
+    let message = err.to_string();
+
+    assert!(message.contains("due_date is mapped to \"Due Date\""), "{}", message);
+    assert!(message.contains("Deadline (date)"), "{}", message);
+    _schema.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_status_validated_against_cached_schema() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "title": [{ "plain_text": "Team board" }],
+            "properties": {
+                "Status": { "id": "s", "type": "status", "status": {
+                    "options": [
+                        { "id": "1", "name": "Not started", "color": "default" },
+                        { "id": "2", "name": "Blocked", "color": "red" }
+                    ],
+                    "groups": []
+                }},
+                "Priority": { "id": "p", "type": "select", "select": {
+                    "options": [{ "id": "a", "name": "P0", "color": "red" }]
+                }}
+            }
+        }).to_string())
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let status = rt.block_on(client.parse_status("blocked"))?;
+    let priority = rt.block_on(client.parse_priority("P0"))?;
+    let invalid = rt.block_on(client.parse_status("Done"));
+
+    assert_eq!(status, TaskStatus::Other("Blocked".to_string()));
+    assert_eq!(priority, TaskPriority::Other("P0".to_string()));
+    assert!(invalid.is_err());
+    _schema.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_tasks_in_the_complete_group_are_not_overdue() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "properties": {
+                "Status": { "id": "s", "type": "status", "status": {
+                    "options": [
+                        { "id": "1", "name": "Not started", "color": "default" },
+                        { "id": "2", "name": "Shipped", "color": "green" }
+                    ],
+                    "groups": [
+                        { "name": "To-do", "option_ids": ["1"] },
+                        { "name": "Complete", "option_ids": ["2"] }
+                    ]
+                }}
+            }
+        }).to_string())
+        .create();
+
+    let _page = mock_server.mock("GET", "/v1/pages/task-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "page",
+            "id": "task-id",
+            "properties": {
+                "Name": { "type": "title", "title": [{ "plain_text": "Release" }] },
+                "Status": { "type": "status", "status": { "name": "Shipped" } },
+                "Due Date": { "type": "date", "date": { "start": "2024-01-10" } }
+            }
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let today = chrono::NaiveDate::from_ymd_opt(2024, 1, 17).unwrap();
+
+    // Before the schema is loaded, only `Done` counts as finished.
+    let task = rt.block_on(client.get_task("task-id"))?;
+    assert_eq!(task.status_group, None);
+    assert!(task.is_overdue(today));
+
+    rt.block_on(client.schema())?;
+    let task = rt.block_on(client.get_task("task-id"))?;
+    assert_eq!(task.status_group, Some(StatusGroup::Complete));
+    assert!(!task.is_overdue(today));
+
+    Ok(())
+}
+
+#[test]
+fn test_init_endpoints() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _me = mock_server.mock("GET", "/v1/users/me")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"object":"user","id":"bot-id","type":"bot","name":"Tasks CLI","bot":{"workspace_name":"Acme"}}"#)
+        .create();
+
+    let search = mock_server.mock("POST", "/v1/search")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+            "filter": { "property": "object", "value": "database" }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "results": [{
+                "object": "database",
+                "id": "team-db",
+                "title": [{ "plain_text": "Team board" }],
+                "properties": { "Name": { "id": "title", "type": "title", "title": {} } }
+            }],
+            "has_more": false,
+            "next_cursor": null
+        }).to_string())
+        .create();
+
+    let patch = mock_server.mock("PATCH", "/v1/databases/database-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": {
+                "Priority": { "select": { "options": [{ "name": "High" }, { "name": "Low" }] } }
+            }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "title": [{ "plain_text": "Team board" }],
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Priority": { "id": "p", "type": "select", "select": { "options": [] } }
+            }
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let bot = rt.block_on(client.me())?;
+    assert_eq!(bot.name.as_deref(), Some("Tasks CLI"));
+    assert_eq!(bot.workspace_name.as_deref(), Some("Acme"));
+
+    let databases = rt.block_on(client.search_databases())?;
+    assert_eq!(databases.len(), 1);
+    assert_eq!(databases[0].title, "Team board");
+    search.assert();
+
+    let priority = PropertyRef::new("Priority", PropertyKind::Select);
+    let schema = rt.block_on(client.create_property(&priority, &["High", "Low"]))?;
+    assert!(schema.fits(&priority));
+    patch.assert();
+
+    let status = PropertyRef::new("Status", PropertyKind::Status);
+    assert!(rt.block_on(client.create_property(&status, &[])).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_init_sends_no_patch() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "properties": { "Name": { "id": "title", "type": "title", "title": {} } }
+        }).to_string())
+        .create();
+
+    let patch = mock_server.mock("PATCH", "/v1/databases/database-id")
+        .expect(0)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let client = client.with_dry_run();
+
+    let priority = PropertyRef::new("Priority", PropertyKind::Select);
+    let schema = rt.block_on(client.create_property(&priority, &["High", "Low"]))?;
+    assert!(schema.fits(&priority));
+    patch.assert();
+
+    let planned = client.take_planned_requests();
+    assert_eq!(planned.len(), 1);
+    assert_eq!(planned[0].method, "PATCH");
+
+    Ok(())
+}
+
+#[test]
+fn test_resolve_task_id_prefixes_urls_and_aliases() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = |id: &str, title: &str| serde_json::json!({
+        "object": "page",
+        "id": id,
+        "properties": {
+            "Name": { "type": "title", "title": [{ "type": "text", "text": { "content": title, "link": null } }] },
+            "Status": { "type": "status", "status": { "name": "Not started" } }
+        }
+    });
+
+    let query = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [
+                page("1a2b3c4d-0000-0000-0000-000000000001", "Write report"),
+                page("1a2b9999-0000-0000-0000-000000000002", "Plan sprint")
+            ],
+            "has_more": false,
+            "next_cursor": null
+        }).to_string())
+        .expect(4)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let id = rt.block_on(resolve_task_id(&client, "1a2b3c", None))?;
+    assert_eq!(id, "1a2b3c4d-0000-0000-0000-000000000001");
+
+    let err = rt.block_on(resolve_task_id(&client, "1a2b", None)).unwrap_err();
+    assert!(err.to_string().contains("matches 2 tasks"));
+    assert!(err.to_string().contains("1a2b9999  Plan sprint"));
+
+    // Full IDs, URLs and aliases need no request.
+    let url = "https://www.notion.so/acme/Write-report-1a2b3c4d000000000000000000000001";
+    assert_eq!(rt.block_on(resolve_task_id(&client, url, None))?, "1a2b3c4d-0000-0000-0000-000000000001");
+
+    let aliases = AliasCache {
+        database_id: "database-id".to_string(),
+        tasks: vec![CachedTask { id: "1a2b9999-0000-0000-0000-000000000002".to_string(), title: "Plan sprint".to_string() }],
+    };
+    assert_eq!(rt.block_on(resolve_task_id(&client, "1", Some(&aliases)))?, "1a2b9999-0000-0000-0000-000000000002");
+
+    // Prefixes are always checked against the whole database: one matching
+    // a listed task and a task missing from the list is still ambiguous.
+    let err = rt.block_on(resolve_task_id(&client, "1a2b", Some(&aliases))).unwrap_err();
+    assert!(err.to_string().contains("1a2b3c4d  Write report"));
+    assert!(err.to_string().contains("1a2b9999  Plan sprint"));
+    let id = rt.block_on(resolve_task_id(&client, "1a2b3c", Some(&aliases)))?;
+    assert_eq!(id, "1a2b3c4d-0000-0000-0000-000000000001");
+    query.assert();
+
+    let other_database = AliasCache { database_id: "other".to_string(), ..aliases };
+    assert!(rt.block_on(resolve_task_id(&client, "1", Some(&other_database))).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_find_by_title_filters_on_every_word() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = |id: &str, title: &str| serde_json::json!({
+        "object": "page",
+        "id": id,
+        "properties": {
+            "Name": { "type": "title", "title": [{ "type": "text", "text": { "content": title, "link": null } }] },
+            "Status": { "type": "status", "status": { "name": "Not started" } }
+        }
+    });
+
+    let results = serde_json::json!({
+        "object": "list",
+        "results": [
+            page("task-2", "Review and rewrite the report"),
+            page("task-1", "Write report"),
+            page("task-3", "Write the monthly report")
+        ],
+        "has_more": false,
+        "next_cursor": null
+    });
+
+    let every_word = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "filter": {
+                "and": [
+                    { "property": "Name", "title": { "contains": "write" } },
+                    { "property": "Name", "title": { "contains": "report" } }
+                ]
+            }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(results.to_string())
+        .create();
+
+    let one_word = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "filter": { "property": "Name", "title": { "contains": "report" } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(results.to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    // An exact title wins over the other matches.
+    let tasks = rt.block_on(find_by_title(&client, "write  report"))?;
+    assert_eq!(tasks.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["task-1"]);
+    every_word.assert();
+
+    let tasks = rt.block_on(find_by_title(&client, "report"))?;
+    assert_eq!(tasks.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), ["task-1", "task-3", "task-2"]);
+    one_word.assert();
+
+    let err = ambiguous_title("report", &tasks);
+    assert!(err.to_string().starts_with("\"report\" matches 3 tasks; use more words or an ID:\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_bulk_apply_reports_each_task() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = |id: &str, title: &str, tags: &[&str]| serde_json::json!({
+        "object": "page",
+        "id": id,
+        "properties": {
+            "Name": { "type": "title", "title": [{ "type": "text", "text": { "content": title, "link": null } }] },
+            "Status": { "type": "status", "status": { "name": "Not started" } },
+            "Tags": { "type": "multi_select", "multi_select": tags.iter().map(|t| serde_json::json!({ "name": t })).collect::<Vec<_>>() }
+        }
+    });
+
+    let _query = mock_server.mock("POST", "/v1/databases/database-id/query")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "list",
+            "results": [page("task-1", "Ship", &["dev"]), page("task-2", "Gone", &[])],
+            "has_more": false,
+            "next_cursor": null
+        }).to_string())
+        .create();
+
+    let updated = mock_server.mock("PATCH", "/v1/pages/task-1")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": {
+                "Tags": { "multi_select": [{ "name": "dev" }, { "name": "sprint-12" }] }
+            }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(page("task-1", "Ship", &["dev", "sprint-12"]).to_string())
+        .create();
+
+    let _missing = mock_server.mock("PATCH", "/v1/pages/task-2")
+        .with_status(404)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "error",
+            "status": 404,
+            "code": "object_not_found",
+            "message": "Could not find page with ID: task-2."
+        }).to_string())
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let tasks = rt.block_on(client.stream_query(TaskQuery::new()).try_collect::<Vec<_>>())?;
+    let action = BulkAction::AddTags(vec!["sprint-12".to_string()]);
+    let outcomes = rt.block_on(bulk::apply(&client, tasks, &action));
+
+    assert_eq!(outcomes.len(), 2);
+    assert_eq!(outcomes[0].task.id, "task-1");
+    let task = outcomes[0].result.as_ref().unwrap().as_ref().unwrap();
+    assert_eq!(task.tags, vec!["dev".to_string(), "sprint-12".to_string()]);
+    updated.assert();
+
+    let err = outcomes[1].result.as_ref().unwrap_err();
+    assert!(matches!(err.downcast_ref::<NotionError>(), Some(NotionError::ObjectNotFound { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_plans_mutations_without_sending_them() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = serde_json::json!({
+        "object": "page",
+        "id": "task-id",
+        "properties": {
+            "Name": { "type": "title", "title": [{ "type": "text", "text": { "content": "Ship" }, "plain_text": "Ship" }] },
+            "Status": { "type": "status", "status": { "name": "Not started" } },
+            "Tags": { "type": "multi_select", "multi_select": [{ "name": "dev" }] }
+        }
+    });
+
+    let get = mock_server.mock("GET", "/v1/pages/task-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(page.to_string())
+        .expect(2)
+        .create();
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Status": { "id": "status", "type": "status", "status": {
+                    "options": [
+                        { "id": "s1", "name": "Backlog" },
+                        { "id": "s2", "name": "Shipped" }
+                    ],
+                    "groups": [
+                        { "id": "g1", "name": "To-do", "option_ids": ["s1"] },
+                        { "id": "g2", "name": "Complete", "option_ids": ["s2"] }
+                    ]
+                } }
+            }
+        }).to_string())
+        .create();
+    let patch = mock_server.mock("PATCH", mockito::Matcher::Any).expect(0).create();
+    let post = mock_server.mock("POST", "/v1/pages").expect(0).create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let client = client.with_dry_run();
+
+    let after = rt.block_on(client.update_task("task-id", &TaskUpdate::new().status(TaskStatus::Done)))?;
+    assert_eq!(after.status, TaskStatus::Done);
+    assert_eq!(after.tags, vec!["dev".to_string()]);
+
+    rt.block_on(client.delete_task("task-id"))?;
+    // Without a status the request leaves it to Notion, and the preview
+    // shows the first To-do status of the database.
+    let created = rt.block_on(client.create_task(&NewTask::new("New task")))?;
+    assert_eq!(created.title, "New task");
+    assert_eq!(created.status, TaskStatus::Other("Backlog".to_string()));
+
+    let planned = client.take_planned_requests();
+    assert_eq!(planned.len(), 3);
+
+    assert_eq!(planned[0].method, "PATCH");
+    assert_eq!(planned[0].url, format!("{}/v1/pages/task-id", mock_server.url()));
+    assert_eq!(planned[0].body, Some(serde_json::json!({
+        "properties": { "Status": { "status": { "name": "Done" } } }
+    })));
+    let changes = planned[0].changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!((changes[0].field, changes[0].before.as_str(), changes[0].after.as_str()), ("status", "Not started", "Done"));
+
+    assert_eq!(planned[1].body, Some(serde_json::json!({ "archived": true })));
+    assert!(planned[1].before.is_some() && planned[1].after.is_none());
+    assert_eq!(planned[2].method, "POST");
+    assert!(planned[2].before.is_none());
+    assert!(planned[2].body.as_ref().is_some_and(|body| body["properties"].get("Status").is_none()));
+
+    assert!(client.take_planned_requests().is_empty());
+    get.assert();
+    patch.assert();
+    post.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_remove_and_set_task_tags() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let page = |tags: &[&str]| serde_json::json!({
+        "object": "page",
+        "id": "task-id",
+        "properties": {
+            "Tags": { "type": "multi_select", "multi_select": tags.iter().map(|t| serde_json::json!({ "name": t })).collect::<Vec<_>>() }
+        }
+    }).to_string();
+
+    let _get = mock_server.mock("GET", "/v1/pages/task-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(page(&["work", "urgent", "q1"]))
+        .create();
+
+    let remove = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": { "Tags": { "multi_select": [{ "name": "work" }, { "name": "q1" }] } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(page(&["work", "q1"]))
+        .create();
+
+    let clear = mock_server.mock("PATCH", "/v1/pages/task-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": { "Tags": { "multi_select": [] } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(page(&[]))
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+
+    let task = rt.block_on(client.remove_task_tags("task-id", "urgent"))?;
+    assert_eq!(task.tags, vec!["work".to_string(), "q1".to_string()]);
+    remove.assert();
+
+    let task = rt.block_on(client.set_task_tags("task-id", ""))?;
+    assert!(task.tags.is_empty());
+    clear.assert();
+
+    Ok(())
+}
+
+#[test]
+fn test_rename_and_remove_tag_options() -> Result<()> {
+    let mut mock_server = mockito::Server::new();
+
+    let _schema = mock_server.mock("GET", "/v1/databases/database-id")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(serde_json::json!({
+            "object": "database",
+            "id": "database-id",
+            "properties": {
+                "Name": { "id": "title", "type": "title", "title": {} },
+                "Tags": { "id": "tags", "type": "multi_select", "multi_select": { "options": [
+                    { "id": "t1", "name": "dev", "color": "blue" },
+                    { "id": "t2", "name": "ops", "color": "red" },
+                    { "id": "t3", "name": "infra", "color": "gray" }
+                ] } }
+            }
+        }).to_string())
+        .create();
+
+    let rename = mock_server.mock("PATCH", "/v1/databases/database-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": { "Tags": { "multi_select": { "options": [
+                { "id": "t1", "name": "development" },
+                { "id": "t2", "name": "ops" },
+                { "id": "t3", "name": "infra" }
+            ] } } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"object":"database","id":"database-id","properties":{}}"#)
+        .expect(1)
+        .create();
+
+    let remove = mock_server.mock("PATCH", "/v1/databases/database-id")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "properties": { "Tags": { "multi_select": { "options": [{ "id": "t2", "name": "ops" }] } } }
+        })))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"object":"database","id":"database-id","properties":{}}"#)
+        .expect(1)
+        .create();
+
+    let (client, rt) = setup_test_client(&mock_server)?;
+    let tags = PropertyRef::new("Tags", PropertyKind::MultiSelect);
+
+    rt.block_on(client.rename_option(&tags, "dev", "development"))?;
+    rename.assert();
+
+    rt.block_on(client.remove_options(&tags, &["dev".to_string(), "infra".to_string()]))?;
+    remove.assert();
+
+    let err = rt.block_on(client.rename_option(&tags, "dev", "ops")).unwrap_err();
+    assert!(err.to_string().contains("already has an option named \"ops\""));
+    let err = rt.block_on(client.rename_option(&tags, "qa", "test")).unwrap_err();
+    assert!(err.to_string().contains("has no option named \"qa\""));
+
+    Ok(())
+}